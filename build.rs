@@ -0,0 +1,29 @@
+use std::{path::Path, process::Command};
+
+fn main() {
+    // Las migraciones van embebidas (sqlx::migrate!): recompilar si se añade o cambia alguna
+    println!("cargo:rerun-if-changed=migrations");
+
+    // Commit del binario para GET /api/admin/health: GIT_SHA (build arg de Docker, donde no
+    // se copia .git) o `git rev-parse`; si no hay ninguno, "unknown"
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    for path in [".git/HEAD", ".git/refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    let sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short=12", "HEAD"])
+                .output()
+                .ok()
+                .filter(|out| out.status.success())
+                .and_then(|out| String::from_utf8(out.stdout).ok())
+                .map(|sha| sha.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_SHA={}", sha);
+}
@@ -0,0 +1,50 @@
+-- Esquema inicial (tablas que existían antes de versionar migraciones).
+-- IF NOT EXISTS: una base creada a mano con este esquema adopta las migraciones sin tocar datos.
+CREATE TABLE IF NOT EXISTS users (
+  id BIGSERIAL PRIMARY KEY,
+  username VARCHAR(100) NOT NULL UNIQUE,
+  email VARCHAR(255) NOT NULL UNIQUE,
+  password_hash TEXT NOT NULL,
+  role VARCHAR(20) NOT NULL DEFAULT 'editor',
+  created_at TIMESTAMPTZ DEFAULT NOW()
+);
+CREATE TABLE IF NOT EXISTS categories (
+  id SERIAL PRIMARY KEY,
+  name VARCHAR(100) NOT NULL,
+  slug VARCHAR(100) NOT NULL UNIQUE,
+  description TEXT
+);
+CREATE TABLE IF NOT EXISTS articles (
+  id BIGSERIAL PRIMARY KEY,
+  title VARCHAR(255) NOT NULL,
+  slug VARCHAR(255) NOT NULL UNIQUE,
+  content TEXT NOT NULL,
+  excerpt TEXT,
+  main_image_url TEXT,
+  video_embed_url TEXT,
+  author_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+  category_id INT REFERENCES categories(id) ON DELETE SET NULL,
+  status VARCHAR(20) DEFAULT 'draft',
+  is_featured BOOLEAN DEFAULT FALSE,
+  is_breaking BOOLEAN DEFAULT FALSE,
+  views_count BIGINT DEFAULT 0,
+  published_at TIMESTAMPTZ,
+  created_at TIMESTAMPTZ DEFAULT NOW(),
+  updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+CREATE TABLE IF NOT EXISTS tags (
+  id SERIAL PRIMARY KEY,
+  name VARCHAR(100) NOT NULL,
+  slug VARCHAR(100) NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS article_tags (
+  article_id BIGINT NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+  tag_id INT NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+  PRIMARY KEY (article_id, tag_id)
+);
+CREATE TABLE IF NOT EXISTS site_config (
+  id INT PRIMARY KEY,
+  live_stream_url TEXT,
+  is_live_active BOOLEAN DEFAULT TRUE,
+  breaking_news_banner TEXT
+);
@@ -0,0 +1,4 @@
+-- Roles válidos (ver models::role::Role)
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_role_check;
+ALTER TABLE users ADD CONSTRAINT users_role_check
+    CHECK (role IN ('admin', 'chief_editor', 'editor', 'contributor'));
@@ -0,0 +1,11 @@
+-- SSO OIDC: estado temporal del flujo authorization-code + PKCE (un solo uso)
+CREATE TABLE IF NOT EXISTS oidc_login_states (
+    state TEXT PRIMARY KEY,
+    code_verifier TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+-- Vínculo con la identidad del IdP (claim `sub`)
+ALTER TABLE users ADD COLUMN IF NOT EXISTS oidc_subject TEXT UNIQUE;
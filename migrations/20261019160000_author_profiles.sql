@@ -0,0 +1,24 @@
+-- Perfil público de autor (bylines y /api/authors/:slug)
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS author_slug VARCHAR(80) UNIQUE,
+    ADD COLUMN IF NOT EXISTS display_name VARCHAR(120),
+    ADD COLUMN IF NOT EXISTS bio TEXT,
+    ADD COLUMN IF NOT EXISTS photo_url TEXT,
+    ADD COLUMN IF NOT EXISTS social_links JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+-- Slug inicial a partir del username (si dos chocan, el más antiguo se lo queda;
+-- el resto lo elige en PUT /api/me/profile)
+UPDATE users u
+SET author_slug = s.slug
+FROM (
+    SELECT id, slug, ROW_NUMBER() OVER (PARTITION BY slug ORDER BY id) AS rn
+    FROM (
+        SELECT id, TRIM(BOTH '-' FROM LOWER(REGEXP_REPLACE(username, '[^a-zA-Z0-9]+', '-', 'g'))) AS slug
+        FROM users
+        WHERE author_slug IS NULL
+    ) candidates
+) s
+WHERE u.id = s.id AND s.rn = 1 AND s.slug <> ''
+  AND NOT EXISTS (SELECT 1 FROM users taken WHERE taken.author_slug = s.slug);
+
+CREATE INDEX IF NOT EXISTS idx_articles_author_published ON articles (author_id, published_at DESC) WHERE status = 'published';
@@ -0,0 +1,20 @@
+-- Créditos por noticia: redacción, foto, cámara, edición. Pueden ser usuarios o externos (solo nombre).
+CREATE TABLE IF NOT EXISTS article_contributors (
+    id BIGSERIAL PRIMARY KEY,
+    article_id BIGINT NOT NULL REFERENCES articles(id) ON DELETE CASCADE,
+    user_id BIGINT REFERENCES users(id) ON DELETE CASCADE,
+    external_name VARCHAR(120),
+    credit_role VARCHAR(20) NOT NULL CHECK (credit_role IN ('author', 'photographer', 'camera', 'editor')),
+    position INT NOT NULL DEFAULT 0,
+    CONSTRAINT article_contributors_who CHECK ((user_id IS NULL) <> (external_name IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_article_contributors_article ON article_contributors (article_id, position);
+CREATE INDEX IF NOT EXISTS idx_article_contributors_user ON article_contributors (user_id) WHERE user_id IS NOT NULL;
+
+-- El autor actual de cada noticia pasa a ser su primer crédito
+INSERT INTO article_contributors (article_id, user_id, credit_role, position)
+SELECT a.id, a.author_id, 'author', 0
+FROM articles a
+WHERE a.author_id IS NOT NULL
+  AND NOT EXISTS (SELECT 1 FROM article_contributors c WHERE c.article_id = a.id);
@@ -0,0 +1,18 @@
+-- Registro de auditoría persistente de acciones privilegiadas y logins
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    actor_email TEXT,            -- Copia del email: sobrevive al borrado del usuario y cubre logins fallidos
+    api_key_id BIGINT,           -- Si la acción llegó con X-API-Key
+    action VARCHAR(64) NOT NULL, -- "article.update", "auth.login_failed", ...
+    entity_type VARCHAR(32) NOT NULL,
+    entity_id TEXT,
+    before JSONB,
+    after JSONB,
+    ip_address TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log (created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log (actor_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log (entity_type, entity_id, created_at DESC);
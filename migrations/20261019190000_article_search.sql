@@ -0,0 +1,6 @@
+-- Búsqueda de noticias (?search=): ILIKE '%texto%' sobre título y contenido.
+-- Índices trigram para no recorrer toda la tabla; `ctv_backend reindex-search` los reconstruye.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_articles_title_trgm ON articles USING GIN (title gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS idx_articles_content_trgm ON articles USING GIN (content gin_trgm_ops);
@@ -0,0 +1,10 @@
+-- Las cuentas SSO dadas de alta sin preferred_username libre recibían el email como username,
+-- y el username es el nombre público del byline cuando no hay display_name. Se cambia por la
+-- parte local + un sufijo (como hace ahora el alta) y se retira el slug que salió del email.
+UPDATE users
+SET username = LEFT(SPLIT_PART(email, '@', 1), 80) || '-' || SUBSTR(MD5(RANDOM()::text), 1, 6),
+    author_slug = CASE
+        WHEN author_slug = TRIM(BOTH '-' FROM REGEXP_REPLACE(LOWER(email), '[^a-z0-9]+', '-', 'g')) THEN NULL
+        ELSE author_slug
+    END
+WHERE username = email AND oidc_subject IS NOT NULL;
@@ -0,0 +1,11 @@
+-- Byline y créditos de cada noticia en el formato de la API (AuthorSummary y Contributor), en un
+-- solo sitio: todas las consultas de noticias hacen JOIN con esta vista. Postgres la expande en
+-- cada consulta, así que filtrar articles sigue usando sus índices.
+CREATE OR REPLACE VIEW article_bylines AS
+SELECT
+    a.id AS article_id,
+    (SELECT json_build_object('id', u.id, 'slug', u.author_slug, 'display_name', COALESCE(u.display_name, u.username), 'photo_url', u.photo_url)
+        FROM users u WHERE u.id = a.author_id) AS author,
+    COALESCE((SELECT json_agg(json_build_object('user_id', c.user_id, 'slug', cu.author_slug, 'name', COALESCE(c.external_name, cu.display_name, cu.username), 'photo_url', cu.photo_url, 'role', c.credit_role) ORDER BY c.position)
+        FROM article_contributors c LEFT JOIN users cu ON cu.id = c.user_id WHERE c.article_id = a.id), '[]') AS contributors
+FROM articles a;
@@ -0,0 +1,412 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use clap::{Parser, Subcommand};
+use std::{
+    collections::HashSet,
+    io::BufRead,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::{Duration, SystemTime},
+};
+use crate::{
+    config::AppConfig,
+    db::DbPool,
+    models::role::Role,
+    utils::{
+        audit::{self, AuditEntry},
+        login_guard,
+        security::hash_password,
+        slug::slugify,
+        validation::password_policy,
+    },
+};
+
+// Línea de comandos del binario: sin subcomando arranca el servidor. Todos los comandos usan la
+// misma configuración (config.rs) y el mismo pool que el servidor HTTP.
+#[derive(Parser)]
+#[command(name = "ctv_backend", about = "Backend del portal de noticias CTV")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    // Compatibilidad: `--migrate` equivale a `migrate`
+    #[arg(long, hide = true)]
+    pub migrate: bool,
+}
+
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Arranca el servidor HTTP (por defecto)
+    Serve,
+    /// Aplica las migraciones pendientes y sale
+    Migrate,
+    /// Crea un usuario administrador (la contraseña se lee de stdin o se genera)
+    CreateAdmin {
+        #[arg(long)]
+        email: String,
+        /// Por defecto, la parte local del email
+        #[arg(long)]
+        username: Option<String>,
+        /// Leer la contraseña de la primera línea de stdin en lugar de generarla
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Cambia la contraseña de un usuario, lo desbloquea y cierra sus sesiones
+    ResetPassword {
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        password_stdin: bool,
+        /// Desactivar también su 2FA (se le pedirá enrolarse de nuevo si es obligatorio)
+        #[arg(long)]
+        reset_2fa: bool,
+    },
+    /// Carga categorías, tags y noticias de ejemplo (idempotente)
+    Seed,
+    /// Reconstruye los índices de búsqueda de noticias y actualiza sus estadísticas
+    ReindexSearch,
+    /// Consulta /readyz del servidor local y sale con 0 si está listo (HEALTHCHECK de Docker)
+    Healthcheck,
+    /// Borra de la carpeta de subidas las imágenes que nada referencia
+    GcUploads {
+        /// Solo listar lo que se borraría
+        #[arg(long)]
+        dry_run: bool,
+        /// No tocar ficheros más recientes (pueden ser de una noticia a medio escribir)
+        #[arg(long, default_value_t = 24)]
+        min_age_hours: u64,
+    },
+}
+
+impl Cli {
+    pub fn command(&self) -> Command {
+        match &self.command {
+            Some(command) => command.clone(),
+            None if self.migrate => Command::Migrate,
+            None => Command::Serve,
+        }
+    }
+}
+
+// Contraseña de stdin (validada con la misma política que el registro) o una aleatoria que se
+// muestra una sola vez
+fn read_or_generate_password(from_stdin: bool) -> Result<(String, bool), String> {
+    if !from_stdin {
+        loop {
+            let mut buf = [0u8; 18];
+            OsRng.fill_bytes(&mut buf);
+            let password = URL_SAFE_NO_PAD.encode(buf);
+            if password_policy(&password).is_ok() {
+                return Ok((password, true));
+            }
+        }
+    }
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line).map_err(|e| format!("No se pudo leer stdin: {}", e))?;
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    password_policy(&password)
+        .map_err(|_| "La contraseña debe tener al menos 8 caracteres, con letras y números".to_string())?;
+    Ok((password, false))
+}
+
+pub async fn create_admin(
+    pool: &DbPool,
+    email: &str,
+    username: Option<String>,
+    password_stdin: bool,
+) -> Result<(), String> {
+    let email = email.trim().to_lowercase();
+    if !email.contains('@') {
+        return Err(format!("Email inválido: {}", email));
+    }
+    let username = username.unwrap_or_else(|| email.split('@').next().unwrap_or_default().to_string());
+    let (password, generated) = read_or_generate_password(password_stdin)?;
+    let password_hash = hash_password(&password)?;
+
+    let user = sqlx::query!(
+        "INSERT INTO users (username, email, password_hash, role, author_slug)
+         VALUES ($1, $2, $3, $4, (SELECT $5::text WHERE $5 <> '' AND NOT EXISTS (SELECT 1 FROM users WHERE author_slug = $5)))
+         RETURNING id, username, email, role",
+        username,
+        email,
+        password_hash,
+        Role::Admin.as_str(),
+        slugify(&username)
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            format!("Ya existe un usuario con ese email o username (para recuperarlo: reset-password --email {})", email)
+        }
+        e => format!("Error creando usuario: {}", e),
+    })?;
+
+    audit::record(
+        pool,
+        AuditEntry::new("user.create", "user", user.id)
+            .after(&serde_json::json!({ "username": user.username, "email": user.email, "role": user.role, "via": "cli" })),
+    )
+    .await;
+    tracing::info!("cli_admin_created user_id={}", user.id);
+
+    println!("Administrador creado: {} (id {})", user.email, user.id);
+    if generated {
+        println!("Contraseña generada (no se volverá a mostrar): {}", password);
+    }
+    Ok(())
+}
+
+pub async fn reset_password(pool: &DbPool, email: &str, password_stdin: bool, reset_2fa: bool) -> Result<(), String> {
+    // Email exacto, como en el login: sin distinguir mayúsculas podría cambiar varias cuentas
+    let email = email.trim();
+    let (password, generated) = read_or_generate_password(password_stdin)?;
+    let password_hash = hash_password(&password)?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let user = sqlx::query!(
+        "UPDATE users SET password_hash = $2 WHERE email = $1 RETURNING id, email",
+        email,
+        password_hash
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| format!("Error actualizando contraseña: {}", e))?;
+    let Some(user) = user else {
+        // Pista para quien lo escribió con otras mayúsculas
+        let similar = sqlx::query_scalar!("SELECT email FROM users WHERE lower(email) = lower($1) ORDER BY id", email)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| format!("Error buscando usuario: {}", e))?;
+        return Err(if similar.is_empty() {
+            format!("No existe ningún usuario con email {}", email)
+        } else {
+            format!("No existe ningún usuario con email {} (parecidos: {})", email, similar.join(", "))
+        });
+    };
+    let user_id = user.id;
+
+    // Quien tenga la contraseña vieja no debe seguir dentro
+    let revoked = sqlx::query!(
+        "UPDATE user_sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+        user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("Error revocando sesiones: {}", e))?
+    .rows_affected();
+
+    if reset_2fa {
+        sqlx::query!(
+            "UPDATE users SET totp_secret = NULL, totp_enabled = FALSE, totp_last_step = NULL WHERE id = $1",
+            user_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Error desactivando 2FA: {}", e))?;
+        sqlx::query!("DELETE FROM user_recovery_codes WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Error borrando códigos de recuperación: {}", e))?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    login_guard::clear_account(pool, &user.email).await.map_err(|e| format!("Error desbloqueando la cuenta: {}", e))?;
+
+    audit::record(
+        pool,
+        AuditEntry::new("user.password_reset", "user", user_id)
+            .after(&serde_json::json!({ "via": "cli", "sessions_revoked": revoked, "two_factor_reset": reset_2fa })),
+    )
+    .await;
+    tracing::info!("cli_password_reset user_id={} sessions_revoked={} reset_2fa={}", user_id, revoked, reset_2fa);
+
+    println!("Contraseña cambiada para {} (id {}); {} sesiones cerradas", user.email, user_id, revoked);
+    if generated {
+        println!("Contraseña generada (no se volverá a mostrar): {}", password);
+    }
+    Ok(())
+}
+
+// Datos de demostración para un entorno nuevo; lo que ya existe (por slug) no se toca
+pub async fn seed(pool: &DbPool) -> Result<(), String> {
+    const CATEGORIES: &[(&str, &str)] = &[
+        ("Nacional", "Actualidad del país"),
+        ("Regional", "Noticias de la región"),
+        ("Deportes", "Resultados y crónicas"),
+        ("Cultura", "Agenda, música y espectáculos"),
+        ("Opinión", "Columnas y editoriales"),
+    ];
+    const TAGS: &[&str] = &["Elecciones", "Fútbol", "Clima", "Economía"];
+    // (título, categoría, destacada, última hora)
+    const ARTICLES: &[(&str, &str, bool, bool)] = &[
+        ("Abren las inscripciones para la feria regional del libro", "Cultura", true, false),
+        ("El equipo local gana el clásico en el último minuto", "Deportes", false, false),
+        ("Alerta por lluvias intensas durante el fin de semana", "Regional", false, true),
+    ];
+
+    let db_error = |e: sqlx::Error| format!("Error cargando datos de ejemplo: {}", e);
+    let mut tx = pool.begin().await.map_err(db_error)?;
+    let mut created = (0, 0, 0);
+
+    for (name, description) in CATEGORIES {
+        created.0 += sqlx::query!(
+            "INSERT INTO categories (name, slug, description) VALUES ($1, $2, $3) ON CONFLICT (slug) DO NOTHING",
+            name,
+            slugify(name),
+            description
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+    }
+
+    for name in TAGS {
+        created.1 += sqlx::query!(
+            "INSERT INTO tags (name, slug) VALUES ($1, $2) ON CONFLICT (slug) DO NOTHING",
+            name,
+            slugify(name)
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+    }
+
+    for (title, category, featured, breaking) in ARTICLES {
+        created.2 += sqlx::query!(
+            r#"
+            INSERT INTO articles (title, slug, content, excerpt, category_id, status, is_featured, is_breaking, published_at)
+            VALUES ($1, $2, $3, $4, (SELECT id FROM categories WHERE slug = $5), 'published', $6, $7, NOW())
+            ON CONFLICT (slug) DO NOTHING
+            "#,
+            title,
+            slugify(title),
+            format!("<p>{}. Texto de ejemplo generado por <code>ctv_backend seed</code>.</p>", title),
+            format!("{} (noticia de ejemplo)", title),
+            slugify(category),
+            featured,
+            breaking
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+    }
+
+    sqlx::query!("INSERT INTO site_config (id, is_live_active) VALUES (1, FALSE) ON CONFLICT (id) DO NOTHING")
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    tracing::info!("cli_seed categories={} tags={} articles={}", created.0, created.1, created.2);
+    println!("Creados: {} categorías, {} tags, {} noticias", created.0, created.1, created.2);
+    Ok(())
+}
+
+// Índices trigram de ?search= (migración article_search); CONCURRENTLY: el servidor puede seguir sirviendo
+pub async fn reindex_search(pool: &DbPool) -> Result<(), String> {
+    for index in ["idx_articles_title_trgm", "idx_articles_content_trgm"] {
+        sqlx::raw_sql(&format!("REINDEX INDEX CONCURRENTLY {}", index))
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Error reindexando {}: {}", index, e))?;
+        println!("Reindexado {}", index);
+    }
+    sqlx::raw_sql("ANALYZE articles")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("Error actualizando estadísticas: {}", e))?;
+    tracing::info!("cli_reindex_search");
+    Ok(())
+}
+
+// Una imagen está en uso si su nombre aparece en la portada o el cuerpo de una noticia o en la foto
+// de un perfil (con ruta /uploads/... o URL absoluta)
+pub async fn gc_uploads(pool: &DbPool, config: &AppConfig, dry_run: bool, min_age_hours: u64) -> Result<(), String> {
+    let dir = &config.uploads.dir;
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("{} no existe: nada que limpiar", dir.display());
+            return Ok(());
+        }
+        Err(e) => return Err(format!("No se pudo leer {}: {}", dir.display(), e)),
+    };
+
+    let referenced: HashSet<String> = sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT m[1] AS "name!" FROM (
+            SELECT regexp_matches(COALESCE(main_image_url, '') || ' ' || content, '/uploads/([A-Za-z0-9._-]+)', 'g') AS m
+            FROM articles
+            UNION ALL
+            SELECT regexp_matches(COALESCE(photo_url, ''), '/uploads/([A-Za-z0-9._-]+)', 'g')
+            FROM users
+        ) refs
+        "#
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Error leyendo imágenes referenciadas: {}", e))?
+    .into_iter()
+    .collect();
+
+    let min_age = Duration::from_secs(min_age_hours * 3600);
+    let (mut removed, mut bytes) = (0u64, 0u64);
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else { continue };
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !metadata.is_file() || referenced.contains(&name) {
+            continue;
+        }
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .unwrap_or_default();
+        if age < min_age {
+            continue;
+        }
+
+        if dry_run {
+            println!("Se borraría {} ({} bytes)", name, metadata.len());
+        } else {
+            std::fs::remove_file(entry.path()).map_err(|e| format!("No se pudo borrar {}: {}", name, e))?;
+            println!("Borrado {} ({} bytes)", name, metadata.len());
+        }
+        removed += 1;
+        bytes += metadata.len();
+    }
+
+    tracing::info!("cli_gc_uploads dry_run={} files={} bytes={}", dry_run, removed, bytes);
+    println!(
+        "{} {} ficheros sin referencias ({} bytes)",
+        if dry_run { "Se borrarían" } else { "Borrados" },
+        removed,
+        bytes
+    );
+    Ok(())
+}
+
+// Sonda para el HEALTHCHECK del contenedor (la imagen no trae curl): pide /readyz al servidor de
+// esta misma máquina, en el puerto de la configuración
+pub async fn healthcheck(config: &AppConfig) -> Result<(), String> {
+    let host = match config.server.host {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    let url = format!("http://{}/readyz", SocketAddr::new(host, config.server.port));
+    let response = reqwest::Client::new()
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .map_err(|e| format!("{}: {}", url, e))?;
+    if !response.status().is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("{}: no está listo: {}", url, body));
+    }
+    Ok(())
+}
@@ -0,0 +1,475 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use ipnet::IpNet;
+use crate::{
+    models::role::Role,
+    utils::{oidc::OidcConfig, rate_limit::Rate},
+};
+
+// Configuración de la aplicación, leída una sola vez al arrancar (ver load).
+//
+// Orden de precedencia: valores por defecto < fichero TOML < variables de entorno.
+// El fichero es CTV_CONFIG si está definido, o ./config.toml si existe (ver config.example.toml).
+// Cada opción tiene su variable de entorno y su clave `[sección] clave` en el TOML
+// (tabla completa en el README). Si algo está mal, load() devuelve TODOS los problemas juntos.
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub jwt: JwtConfig,
+    pub cors: CorsConfig,
+    pub uploads: UploadsConfig,
+    pub metrics: MetricsConfig,
+    pub rate_limit: RateLimitConfig,
+    pub cache: CacheConfig,
+    // None si el SSO no está configurado (sin OIDC_ISSUER_URL)
+    pub oidc: Option<OidcConfig>,
+}
+
+pub struct ServerConfig {
+    pub host: IpAddr,
+    pub port: u16,
+    // Decide valores por defecto de seguridad (orígenes CORS, HSTS)
+    pub environment: Environment,
+    // Plazo desde SIGTERM para terminar peticiones y tareas en curso antes de cortarlas
+    pub shutdown_timeout_secs: u64,
+    // Proxies (IPs o redes) cuyo X-Forwarded-For creemos; sin ninguno, la IP del cliente es la
+    // del socket (ver utils::request_info)
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+    // Aplicar migraciones pendientes al arrancar (si no, el arranque falla si las hay)
+    pub migrate_on_start: bool,
+}
+
+// Duración de los tokens que emitimos
+pub struct AuthConfig {
+    pub session_ttl_hours: i64,
+    pub two_factor_challenge_minutes: i64,
+    // Token fijo para crear el primer admin (si no, se genera uno al arrancar; ver utils::bootstrap)
+    pub setup_token: Option<String>,
+}
+
+pub struct JwtConfig {
+    pub keys_dir: PathBuf,
+    pub active_kid: String,
+    pub issuer: String,
+    pub audience: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
+    Development,
+    Production,
+}
+
+impl FromStr for Environment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "development" => Ok(Environment::Development),
+            "production" => Ok(Environment::Production),
+            other => Err(format!("entorno desconocido: {}", other)),
+        }
+    }
+}
+
+pub struct CorsConfig {
+    // Orígenes que pueden llamar a la API desde un navegador; ["*"] = cualquiera.
+    // Sin configurar: los del frontend en local en development, ninguno en production
+    pub allowed_origins: Vec<String>,
+    // Access-Control-Allow-Credentials (cookies / Authorization con credentials: "include")
+    pub allow_credentials: bool,
+}
+
+impl CorsConfig {
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|o| o == "*")
+    }
+}
+
+// Astro (4321) y el propio backend en local
+const DEVELOPMENT_ORIGINS: [&str; 3] = ["http://localhost:4321", "http://127.0.0.1:4321", "http://localhost:3000"];
+
+pub struct UploadsConfig {
+    pub dir: PathBuf,
+    pub max_image_bytes: usize,
+}
+
+pub struct MetricsConfig {
+    pub enabled: bool,
+    // Bearer que exige /metrics (None = abierto)
+    pub token: Option<String>,
+    // Listener aparte solo para /metrics (None = en el de la API)
+    pub port: Option<u16>,
+}
+
+// Límite por grupo de rutas (ver utils::rate_limit): "300/min", "10/s", "1000/h" u "off"
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub public_read: Rate,
+    pub search: Rate,
+    pub views: Rate,
+    pub auth: Rate,
+    pub uploads: Rate,
+}
+
+// Caché de respuestas públicas (ver utils::cache)
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub ttl_secs: u64,
+    // Tope de memoria para todas las entradas juntas
+    pub max_bytes: usize,
+}
+
+impl ServerConfig {
+    pub fn addr(&self) -> SocketAddr {
+        SocketAddr::new(self.host, self.port)
+    }
+}
+
+// Todos los problemas encontrados al cargar la configuración
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "configuración inválida ({} problemas):", self.0.len())?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl AppConfig {
+    // `serving`: si arranca la API (serve). Las opciones que solo usa el servidor, como la clave
+    // JWT activa, solo son obligatorias entonces
+    pub fn load(serving: bool) -> Result<AppConfig, ConfigError> {
+        let mut source = Source::open();
+
+        let mut server = ServerConfig {
+            host: source.parse("HOST", "server", "host", IpAddr::from([0, 0, 0, 0])),
+            port: source.parse("PORT", "server", "port", 3000),
+            environment: source.parse("APP_ENV", "server", "environment", Environment::Production),
+            shutdown_timeout_secs: source.parse("SHUTDOWN_TIMEOUT_SECS", "server", "shutdown_timeout_secs", 25),
+            trusted_proxies: Vec::new(),
+        };
+        // Una IP suelta vale como red /32 (o /128)
+        for entry in source.list("TRUSTED_PROXIES", "server", "trusted_proxies") {
+            match entry.parse::<IpNet>().or_else(|_| entry.parse::<IpAddr>().map(IpNet::from)) {
+                Ok(net) => server.trusted_proxies.push(net),
+                Err(_) => source.problem(format!(
+                    "TRUSTED_PROXIES / [server] trusted_proxies: '{}' no es una IP ni una red CIDR",
+                    entry
+                )),
+            }
+        }
+
+        let database = DatabaseConfig {
+            url: source.required("DATABASE_URL", "database", "url"),
+            max_connections: source.parse("DATABASE_MAX_CONNECTIONS", "database", "max_connections", 5),
+            acquire_timeout_secs: source.parse("DATABASE_ACQUIRE_TIMEOUT_SECS", "database", "acquire_timeout_secs", 5),
+            migrate_on_start: source.parse("MIGRATE_ON_START", "database", "migrate_on_start", false),
+        };
+        if !database.url.is_empty()
+            && !database.url.starts_with("postgres://")
+            && !database.url.starts_with("postgresql://")
+        {
+            source.problem("DATABASE_URL / [database] url: debe empezar por postgres://");
+        }
+        if database.max_connections == 0 {
+            source.problem("DATABASE_MAX_CONNECTIONS / [database] max_connections: debe ser al menos 1");
+        }
+
+        let auth = AuthConfig {
+            session_ttl_hours: source.parse("SESSION_TTL_HOURS", "auth", "session_ttl_hours", 24),
+            two_factor_challenge_minutes: source.parse("TWO_FACTOR_CHALLENGE_MINUTES", "auth", "two_factor_challenge_minutes", 5),
+            setup_token: source.optional("SETUP_TOKEN", "auth", "setup_token"),
+        };
+        // Con techo: un valor enorme no cabe en chrono::Duration y haría fallar el primer login
+        if !(1..=8760).contains(&auth.session_ttl_hours) {
+            source.problem("SESSION_TTL_HOURS / [auth] session_ttl_hours: debe estar entre 1 y 8760 (un año)");
+        }
+        if !(1..=60).contains(&auth.two_factor_challenge_minutes) {
+            source.problem("TWO_FACTOR_CHALLENGE_MINUTES / [auth] two_factor_challenge_minutes: debe estar entre 1 y 60");
+        }
+        if auth.setup_token.as_ref().is_some_and(|t| t.len() < 16) {
+            source.problem("SETUP_TOKEN / [auth] setup_token: debe tener al menos 16 caracteres");
+        }
+
+        let jwt = JwtConfig {
+            keys_dir: source.string("JWT_KEYS_DIR", "jwt", "keys_dir", "keys").into(),
+            active_kid: if serving {
+                source.required("JWT_ACTIVE_KID", "jwt", "active_kid")
+            } else {
+                source.string("JWT_ACTIVE_KID", "jwt", "active_kid", "")
+            },
+            issuer: source.string("JWT_ISSUER", "jwt", "issuer", "ctv_backend"),
+            audience: source.string("JWT_AUDIENCE", "jwt", "audience", "ctv_api"),
+        };
+
+        let mut cors = CorsConfig {
+            allowed_origins: source.list("CORS_ALLOWED_ORIGINS", "cors", "allowed_origins"),
+            allow_credentials: source.parse("CORS_ALLOW_CREDENTIALS", "cors", "allow_credentials", false),
+        };
+        if cors.allowed_origins.is_empty() && server.environment == Environment::Development {
+            cors.allowed_origins = DEVELOPMENT_ORIGINS.iter().map(|o| o.to_string()).collect();
+        }
+        if cors.allows_any_origin() {
+            if cors.allowed_origins.len() > 1 {
+                source.problem("CORS_ALLOWED_ORIGINS / [cors] allowed_origins: '*' no se combina con otros orígenes");
+            }
+            // El navegador lo rechazaría igual: con credenciales el origen tiene que ser explícito
+            if cors.allow_credentials {
+                source.problem("CORS_ALLOW_CREDENTIALS / [cors] allow_credentials: no se admite con allowed_origins = '*'");
+            }
+        }
+        for origin in cors.allowed_origins.iter().filter(|o| *o != "*") {
+            if !is_origin(origin) {
+                source.problem(format!(
+                    "CORS_ALLOWED_ORIGINS / [cors] allowed_origins: '{}' no es un origen (https://dominio[:puerto])",
+                    origin
+                ));
+            }
+        }
+
+        let uploads = UploadsConfig {
+            dir: source.string("UPLOAD_DIR", "uploads", "dir", "uploads").into(),
+            max_image_bytes: source.parse("UPLOAD_MAX_IMAGE_BYTES", "uploads", "max_image_bytes", 5 * 1024 * 1024),
+        };
+        if uploads.max_image_bytes == 0 {
+            source.problem("UPLOAD_MAX_IMAGE_BYTES / [uploads] max_image_bytes: debe ser mayor que 0");
+        }
+
+        let metrics = MetricsConfig {
+            enabled: source.parse("METRICS_ENABLED", "metrics", "enabled", true),
+            token: source.optional("METRICS_TOKEN", "metrics", "token"),
+            port: Some(source.parse("METRICS_PORT", "metrics", "port", 0u16)).filter(|p| *p != 0),
+        };
+        if metrics.token.as_ref().is_some_and(|t| t.len() < 16) {
+            source.problem("METRICS_TOKEN / [metrics] token: debe tener al menos 16 caracteres");
+        }
+        if metrics.port == Some(server.port) {
+            source.problem("METRICS_PORT / [metrics] port: debe ser distinto de PORT (o no definirse)");
+        }
+
+        let rate_limit = RateLimitConfig {
+            enabled: source.parse("RATE_LIMIT_ENABLED", "rate_limit", "enabled", true),
+            public_read: source.parse("RATE_LIMIT_PUBLIC_READ", "rate_limit", "public_read", Rate::per_minute(300)),
+            search: source.parse("RATE_LIMIT_SEARCH", "rate_limit", "search", Rate::per_minute(30)),
+            views: source.parse("RATE_LIMIT_VIEWS", "rate_limit", "views", Rate::per_minute(30)),
+            auth: source.parse("RATE_LIMIT_AUTH", "rate_limit", "auth", Rate::per_minute(10)),
+            uploads: source.parse("RATE_LIMIT_UPLOADS", "rate_limit", "uploads", Rate::per_minute(30)),
+        };
+
+        let cache = CacheConfig {
+            enabled: source.parse("CACHE_ENABLED", "cache", "enabled", true),
+            ttl_secs: source.parse("CACHE_TTL_SECS", "cache", "ttl_secs", 30),
+            max_bytes: source.parse("CACHE_MAX_BYTES", "cache", "max_bytes", 8 * 1024 * 1024),
+        };
+        if cache.enabled && cache.ttl_secs == 0 {
+            source.problem("CACHE_TTL_SECS / [cache] ttl_secs: debe ser al menos 1 (o CACHE_ENABLED=false)");
+        }
+        if cache.enabled && cache.max_bytes == 0 {
+            source.problem("CACHE_MAX_BYTES / [cache] max_bytes: debe ser mayor que 0 (o CACHE_ENABLED=false)");
+        }
+
+        let oidc = load_oidc(&mut source);
+
+        source.finish()?;
+        Ok(AppConfig { server, database, auth, jwt, cors, uploads, metrics, rate_limit, cache, oidc })
+    }
+}
+
+// SSO OIDC: se activa con OIDC_ISSUER_URL; a partir de ahí, lo que falte o esté mal es un error
+// (antes se desactivaba en silencio)
+fn load_oidc(source: &mut Source) -> Option<OidcConfig> {
+    let issuer_url = source.optional("OIDC_ISSUER_URL", "oidc", "issuer_url");
+    let client_id = source.optional("OIDC_CLIENT_ID", "oidc", "client_id");
+    let client_secret = source.optional("OIDC_CLIENT_SECRET", "oidc", "client_secret");
+    let redirect_url = source.optional("OIDC_REDIRECT_URL", "oidc", "redirect_url");
+    let groups_claim = source.string("OIDC_GROUPS_CLAIM", "oidc", "groups_claim", "groups");
+    let role_map = source.list("OIDC_ROLE_MAP", "oidc", "role_map");
+    let post_login_redirect = source.optional("OIDC_POST_LOGIN_REDIRECT", "oidc", "post_login_redirect");
+
+    let issuer_url = issuer_url?;
+    let mut valid = true;
+    for (name, value) in [
+        ("OIDC_ISSUER_URL / [oidc] issuer_url", Some(&issuer_url)),
+        ("OIDC_REDIRECT_URL / [oidc] redirect_url", redirect_url.as_ref()),
+        ("OIDC_POST_LOGIN_REDIRECT / [oidc] post_login_redirect", post_login_redirect.as_ref()),
+    ] {
+        if let Some(value) = value
+            && !is_http_url(value)
+        {
+            source.problem(format!("{}: '{}' no es una URL http(s)", name, value));
+            valid = false;
+        }
+    }
+    if client_id.is_none() {
+        source.problem("OIDC_CLIENT_ID / [oidc] client_id: obligatorio con OIDC_ISSUER_URL");
+        valid = false;
+    }
+    if redirect_url.is_none() {
+        source.problem("OIDC_REDIRECT_URL / [oidc] redirect_url: obligatorio con OIDC_ISSUER_URL");
+        valid = false;
+    }
+
+    // "grupo=rol": gana el rol más alto, sin coincidencia no hay acceso
+    let mut roles = Vec::new();
+    for entry in role_map {
+        match entry.split_once('=').map(|(group, role)| (group.trim(), role.trim().parse::<Role>())) {
+            Some((group, Ok(role))) if !group.is_empty() => roles.push((group.to_string(), role)),
+            _ => {
+                source.problem(format!("OIDC_ROLE_MAP / [oidc] role_map: entrada inválida '{}' (grupo=rol)", entry));
+                valid = false;
+            }
+        }
+    }
+
+    if !valid {
+        return None;
+    }
+    Some(OidcConfig {
+        issuer_url: issuer_url.trim_end_matches('/').to_string(),
+        client_id: client_id?,
+        client_secret,
+        redirect_url: redirect_url?,
+        groups_claim,
+        role_map: roles,
+        post_login_redirect,
+    })
+}
+
+fn is_http_url(value: &str) -> bool {
+    matches!(reqwest::Url::parse(value), Ok(url) if matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+}
+
+// Origen CORS: esquema + host (+ puerto), sin ruta
+fn is_origin(value: &str) -> bool {
+    is_http_url(value) && reqwest::Url::parse(value).is_ok_and(|url| url.path() == "/" && !value.ends_with('/'))
+}
+
+// Valores del entorno y del fichero TOML, acumulando problemas en lugar de fallar al primero
+struct Source {
+    file: toml::Table,
+    file_name: Option<PathBuf>,
+    used: HashSet<(&'static str, &'static str)>,
+    problems: Vec<String>,
+}
+
+impl Source {
+    fn open() -> Source {
+        let mut source = Source { file: toml::Table::new(), file_name: None, used: HashSet::new(), problems: Vec::new() };
+
+        let path = match std::env::var("CTV_CONFIG") {
+            Ok(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => Some(PathBuf::from("config.toml")).filter(|p| p.exists()),
+        };
+        let Some(path) = path else { return source };
+
+        match std::fs::read_to_string(&path) {
+            Ok(text) => match text.parse::<toml::Table>() {
+                Ok(table) => source.file = table,
+                Err(e) => source.problem(format!("{}: TOML inválido: {}", path.display(), e)),
+            },
+            Err(e) => source.problem(format!("{}: no se pudo leer: {}", path.display(), e)),
+        }
+        source.file_name = Some(path);
+        source
+    }
+
+    fn problem(&mut self, problem: impl Into<String>) {
+        self.problems.push(problem.into());
+    }
+
+    // Variable de entorno (si no está vacía) o, si no, `[section] key` del fichero
+    fn raw(&mut self, env: &str, section: &'static str, key: &'static str) -> Option<String> {
+        self.used.insert((section, key));
+        if let Ok(value) = std::env::var(env)
+            && !value.is_empty()
+        {
+            return Some(value);
+        }
+        let value = self.file.get(section)?.get(key)?;
+        let text = match value {
+            toml::Value::String(s) => s.clone(),
+            toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => value.to_string(),
+            toml::Value::Array(items) => items
+                .iter()
+                .map(|item| item.as_str().map(str::to_string).unwrap_or_else(|| item.to_string()))
+                .collect::<Vec<_>>()
+                .join(","),
+            _ => {
+                self.problem(format!("[{}] {}: tipo no soportado", section, key));
+                return None;
+            }
+        };
+        Some(text).filter(|t| !t.is_empty())
+    }
+
+    fn optional(&mut self, env: &str, section: &'static str, key: &'static str) -> Option<String> {
+        self.raw(env, section, key)
+    }
+
+    fn string(&mut self, env: &str, section: &'static str, key: &'static str, default: &str) -> String {
+        self.raw(env, section, key).unwrap_or_else(|| default.to_string())
+    }
+
+    fn required(&mut self, env: &str, section: &'static str, key: &'static str) -> String {
+        self.raw(env, section, key).unwrap_or_else(|| {
+            self.problem(format!("{} / [{}] {}: obligatorio", env, section, key));
+            String::new()
+        })
+    }
+
+    fn parse<T: FromStr>(&mut self, env: &str, section: &'static str, key: &'static str, default: T) -> T {
+        let Some(text) = self.raw(env, section, key) else { return default };
+        text.trim().parse().unwrap_or_else(|_| {
+            self.problem(format!("{} / [{}] {}: valor inválido '{}'", env, section, key, text));
+            default
+        })
+    }
+
+    // Lista separada por comas en el entorno, o array en el TOML
+    fn list(&mut self, env: &str, section: &'static str, key: &'static str) -> Vec<String> {
+        self.raw(env, section, key)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
+    // Claves del fichero que nadie leyó: casi siempre una errata
+    fn finish(mut self) -> Result<(), ConfigError> {
+        let file_name = self.file_name.as_deref().map(Path::display).map(|d| d.to_string()).unwrap_or_default();
+        let mut unknown = Vec::new();
+        for (section, value) in &self.file {
+            let Some(table) = value.as_table() else {
+                unknown.push(format!("{}: '{}' debería ser una sección [{}]", file_name, section, section));
+                continue;
+            };
+            for key in table.keys() {
+                if !self.used.iter().any(|(s, k)| s == section && k == key) {
+                    unknown.push(format!("{}: clave desconocida [{}] {}", file_name, section, key));
+                }
+            }
+        }
+        self.problems.extend(unknown);
+
+        if self.problems.is_empty() { Ok(()) } else { Err(ConfigError(self.problems)) }
+    }
+}
@@ -0,0 +1,78 @@
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use std::collections::HashMap;
+use crate::db::DbPool;
+
+// Migraciones de migrations/ embebidas en el binario (build.rs recompila si cambian).
+// Se aplican con `--migrate` o MIGRATE_ON_START=true; sin eso, check() impide arrancar
+// contra un esquema que no corresponde a este binario.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+pub async fn run(pool: &DbPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await?;
+    if let Some(latest) = MIGRATOR.iter().last() {
+        tracing::info!("migrations_applied latest={} ({})", latest.version, latest.description);
+    }
+    Ok(())
+}
+
+// Estado del esquema cuando corresponde a este binario
+pub struct SchemaStatus {
+    // Última migración aplicada en la base
+    pub latest_applied: Option<i64>,
+    // Aplicadas en la base pero desconocidas para este binario (otra instancia más nueva ya migró)
+    pub ahead: Vec<i64>,
+}
+
+// Compara las migraciones aplicadas en la base con las embebidas. Devuelve todos los problemas:
+// migraciones pendientes, a medio aplicar o modificadas después de aplicarse.
+pub async fn check(pool: &DbPool) -> Result<(), Vec<String>> {
+    let status = status(pool).await?;
+    // Durante un despliegue la instancia vieja sigue sirviendo
+    for version in status.ahead {
+        tracing::warn!("schema_ahead version={}: migración aplicada que este binario no conoce", version);
+    }
+    Ok(())
+}
+
+// Lo mismo que check, sin loguear: también lo usa /readyz en cada sondeo
+pub async fn status(pool: &DbPool) -> Result<SchemaStatus, Vec<String>> {
+    let db_error = |e: MigrateError| vec![format!("no se pudo leer el estado de las migraciones: {}", e)];
+
+    let mut conn = pool.acquire().await.map_err(|e| db_error(e.into()))?;
+    let tracked = sqlx::query_scalar!(r#"SELECT to_regclass('_sqlx_migrations') IS NOT NULL AS "tracked!""#)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| db_error(e.into()))?;
+
+    let (dirty, applied) = if tracked {
+        let dirty = conn.dirty_version().await.map_err(db_error)?;
+        let applied = conn.list_applied_migrations().await.map_err(db_error)?;
+        (dirty, applied)
+    } else {
+        (None, Vec::new())
+    };
+
+    let mut problems = Vec::new();
+    if let Some(version) = dirty {
+        problems.push(format!("la migración {} falló a medias: revisar la base a mano", version));
+    }
+
+    let applied: HashMap<i64, _> = applied.into_iter().map(|m| (m.version, m.checksum)).collect();
+    for migration in MIGRATOR.iter() {
+        match applied.get(&migration.version) {
+            None => problems.push(format!("migración pendiente: {} ({})", migration.version, migration.description)),
+            Some(checksum) if *checksum != migration.checksum => problems.push(format!(
+                "la migración {} ({}) cambió después de aplicarse",
+                migration.version, migration.description
+            )),
+            Some(_) => {}
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+    let mut ahead: Vec<i64> = applied.keys().copied().filter(|v| !MIGRATOR.iter().any(|m| m.version == *v)).collect();
+    ahead.sort();
+    Ok(SchemaStatus { latest_applied: applied.keys().max().copied(), ahead })
+}
@@ -1,17 +1,18 @@
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
-use std::env;
+use std::time::Duration;
+use crate::config::DatabaseConfig;
+
+pub mod migrations;
 
 // Definimos un alias para "Pool<Postgres>"
 pub type DbPool = Pool<Postgres>;
 
-pub async fn init_db() -> DbPool {
-    // Leemos la URL del archivo .env
-    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL no está definido en .env");
-
-    // Creamos el pool de conexiones
+pub async fn init_db(config: &DatabaseConfig) -> DbPool {
+    // Creamos el pool de conexiones (tamaño y espera máxima por conexión según la config)
     PgPoolOptions::new()
-        .max_connections(5) // Máximo 5 conexiones simultáneas (ajustable)
-        .connect(&db_url)
+        .max_connections(config.max_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .connect(&config.url)
         .await
         .expect("Error al conectar a la Base de Datos. ¿Está corriendo Postgres?")
-}
\ No newline at end of file
+}
@@ -0,0 +1,236 @@
+use axum::{
+    async_trait,
+    extract::{
+        multipart::MultipartRejection,
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        FromRequest, FromRequestParts, Request,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Map, Value};
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+use crate::{models::role::Permission, utils::request_id};
+
+// Error único de la API. Toda respuesta de error tiene la forma
+//   {"code": "not_found", "message": "Noticia no encontrada", "details": {...}, "request_id": "..."}
+// `code` es estable (para el frontend), `message` es para personas y `details` es opcional
+// (campos inválidos, permiso que falta, restricción violada...).
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    details: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'a str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<&'a Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        ApiError { status, code, message: message.into(), details: None }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "bad_request", message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "unauthorized", message)
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "forbidden", message)
+    }
+
+    // 403 que dice exactamente qué permiso falta
+    pub fn missing_permission(permission: Permission) -> Self {
+        Self::new(
+            StatusCode::FORBIDDEN,
+            "missing_permission",
+            format!("No tienes el permiso '{}'", permission.as_str()),
+        )
+        .with_details(json!({ "permission": permission.as_str() }))
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, "conflict", message)
+    }
+
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, "validation_failed", message)
+    }
+
+    // 422 con errores por campo: {"fields": {"title": [{"code": "length", "message": "..."}]}}
+    pub fn invalid_fields(fields: Map<String, Value>) -> Self {
+        Self::unprocessable("Datos inválidos").with_details(json!({ "fields": fields }))
+    }
+
+    pub fn bad_gateway(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_GATEWAY, "upstream_error", message)
+    }
+
+    // 500 sin detalles: la causa va al log, nunca al cliente
+    pub fn internal() -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Error interno")
+    }
+
+    // Para `.map_err(ApiError::db("Error buscando noticia"))?`: registra el error con contexto y
+    // traduce las violaciones de restricciones (unicidad -> 409, FK/CHECK -> 422); el resto es 500
+    pub fn db(context: &'static str) -> impl FnOnce(sqlx::Error) -> ApiError {
+        move |e| {
+            if let sqlx::Error::RowNotFound = e {
+                return ApiError::not_found("Recurso no encontrado");
+            }
+            if let sqlx::Error::Database(db) = &e {
+                let constraint = db.constraint().map(str::to_string);
+                let error = if db.is_unique_violation() {
+                    Some(ApiError::conflict("El recurso ya existe"))
+                } else if db.is_foreign_key_violation() {
+                    Some(ApiError::unprocessable("Hace referencia a un recurso inexistente"))
+                } else if db.is_check_violation() {
+                    Some(ApiError::unprocessable("Valor no permitido"))
+                } else {
+                    None
+                };
+                if let Some(error) = error {
+                    tracing::info!("{}: restricción {:?} violada", context, constraint);
+                    return error.with_details(json!({ "constraint": constraint }));
+                }
+            }
+            tracing::error!("{}: {:?}", context, e);
+            ApiError::internal()
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        ApiError::db("Error de base de datos")(e)
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = Map::new();
+        collect_field_errors(&errors, "", &mut fields);
+        ApiError::invalid_fields(fields)
+    }
+}
+
+// Aplana los errores anidados: "contributors[1].name", "author.slug"...
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, out: &mut Map<String, Value>) {
+    for (field, kind) in errors.errors() {
+        // Los errores de `schema(...)` (de la estructura entera) van bajo "__all__"
+        let path = match (prefix, field.as_ref()) {
+            ("", field) => field.to_string(),
+            (prefix, "__all__") => prefix.to_string(),
+            (prefix, field) => format!("{}.{}", prefix, field),
+        };
+        match kind {
+            ValidationErrorsKind::Field(list) => {
+                let messages = list
+                    .iter()
+                    .map(|e| {
+                        let message = e.message.clone().unwrap_or_else(|| e.code.clone());
+                        json!({ "code": e.code, "message": message })
+                    })
+                    .collect();
+                out.insert(path, Value::Array(messages));
+            }
+            ValidationErrorsKind::Struct(inner) => collect_field_errors(inner, &path, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, inner) in items {
+                    collect_field_errors(inner, &format!("{}[{}]", path, index), out);
+                }
+            }
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            code: self.code,
+            message: &self.message,
+            details: self.details.as_ref(),
+            request_id: request_id::current(),
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+// Rechazos de los extractores de axum (JSON mal formado, path o query inválidos) con nuestro formato
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::new(rejection.status(), "invalid_body", rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        ApiError::new(rejection.status(), "invalid_path", rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::new(rejection.status(), "invalid_query", rejection.body_text())
+    }
+}
+
+impl From<MultipartRejection> for ApiError {
+    fn from(rejection: MultipartRejection) -> Self {
+        ApiError::new(rejection.status(), "invalid_body", rejection.body_text())
+    }
+}
+
+// Extractores equivalentes a Json/Path/Query pero que rechazan con ApiError
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub struct ApiJson<T>(pub T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct ApiPath<T>(pub T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct ApiQuery<T>(pub T);
+
+// ApiJson + reglas de `#[derive(Validate)]`: un payload inválido es 422 antes de llegar al handler
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let ApiJson(value) = ApiJson::<T>::from_request(req, state).await?;
+        value.validate()?;
+        Ok(ValidJson(value))
+    }
+}
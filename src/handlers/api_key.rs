@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiPath, ValidJson},
+    models::{
+        api_key::{ApiKey, CreateApiKeySchema, CreatedApiKey},
+        role::Permission,
+        user::Claims,
+    },
+    utils::{
+        api_keys,
+        audit::{self, AuditEntry},
+        permissions,
+        request_info::ClientIp,
+    },
+};
+
+// GET /api/admin/api-keys (api_keys.manage)
+pub async fn list_api_keys_handler(State(pool): State<DbPool>) -> Result<impl IntoResponse, ApiError> {
+    let keys = sqlx::query_as!(
+        ApiKey,
+        r#"
+        SELECT id, name, prefix, permissions, created_by, created_at, expires_at, last_used_at, revoked_at
+        FROM api_keys
+        ORDER BY created_at DESC
+        "#
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(ApiError::db("Error listando API keys"))?;
+
+    Ok((StatusCode::OK, Json(keys)))
+}
+
+// POST /api/admin/api-keys (api_keys.manage) - la key completa solo se devuelve aquí
+pub async fn create_api_key_handler(
+    State(pool): State<DbPool>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+    ValidJson(body): ValidJson<CreateApiKeySchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    let scopes = body
+        .permissions
+        .iter()
+        .map(|name| name.parse::<Permission>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(ApiError::bad_request)?;
+
+    // Una key nunca puede más que quien la crea
+    for permission in scopes.iter() {
+        permissions::check(&claims, *permission)?;
+    }
+
+    let (prefix, key) = api_keys::generate();
+    let permission_names: Vec<String> = scopes.iter().map(|p| p.as_str().to_string()).collect();
+
+    let api_key = sqlx::query_as!(
+        ApiKey,
+        r#"
+        INSERT INTO api_keys (name, prefix, key_hash, permissions, created_by, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, name, prefix, permissions, created_by, created_at, expires_at, last_used_at, revoked_at
+        "#,
+        body.name,
+        prefix,
+        api_keys::hash(&key),
+        &permission_names,
+        claims.user_id,
+        body.expires_at
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(ApiError::db("Error creando API key"))?;
+
+    tracing::info!(
+        "api_key_created id={} prefix={} permissions={:?} by_user={}",
+        api_key.id,
+        api_key.prefix,
+        api_key.permissions,
+        claims.user_id
+    );
+    audit::record(
+        &pool,
+        AuditEntry::new("api_key.create", "api_key", api_key.id)
+            .actor(&claims)
+            .ip(&ip)
+            .after(&api_key),
+    )
+    .await;
+    Ok((StatusCode::CREATED, Json(CreatedApiKey { api_key, key })))
+}
+
+// DELETE /api/admin/api-keys/:id (api_keys.manage) - revocación inmediata
+pub async fn revoke_api_key_handler(
+    ApiPath(id): ApiPath<i64>,
+    State(pool): State<DbPool>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ApiError> {
+    let result = sqlx::query!(
+        "UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL",
+        id
+    )
+    .execute(&pool)
+    .await
+    .map_err(ApiError::db("Error revocando API key"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("API key no encontrada"));
+    }
+    tracing::info!("api_key_revoked id={} by_user={}", id, claims.user_id);
+    audit::record(
+        &pool,
+        AuditEntry::new("api_key.revoke", "api_key", id)
+            .actor(&claims)
+            .ip(&ip),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,30 +1,59 @@
 use axum::{
-    extract::{Json, Path, Query, State},
+    extract::{Json, State},
     Extension,
     http::StatusCode,
     response::IntoResponse,
 };
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use serde_json::{json, Map};
+use sqlx::{Postgres, Transaction};
 use uuid::Uuid;
 use tracing;
-use crate::{db::DbPool, models::article::{Article, CreateArticleSchema}, models::user::Claims};
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiPath, ApiQuery, ValidJson},
+    models::{
+        article::{Article, Contributor, ContributorInput, ContributorRole, CreateArticleSchema, MAX_CONTRIBUTORS},
+        author::AuthorSummary,
+    },
+    models::{role::Permission, user::Claims},
+    utils::{
+        audit::{self, AuditEntry},
+        cache::{CacheKey, ResponseCache},
+        metrics,
+        permissions,
+        request_info::ClientIp,
+        slug::slugify,
+        validation,
+    },
+};
+use validator::Validate;
 
-#[derive(Debug, Deserialize)]
+// Mismas reglas que CreateArticleSchema; los campos ausentes no se tocan
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateArticleSchema {
+    #[validate(length(max = 255, message = "Máximo 255 caracteres"), custom(function = "validation::not_blank"))]
     pub title: Option<String>,
+    #[validate(length(max = 200000, message = "Máximo 200000 caracteres"), custom(function = "validation::not_blank"))]
     pub content: Option<String>,
+    #[validate(length(max = 500, message = "Máximo 500 caracteres"))]
     pub excerpt: Option<String>,
     pub category_id: Option<i32>,
+    #[validate(length(max = 500, message = "Máximo 500 caracteres"), custom(function = "validation::image_url"))]
     pub main_image_url: Option<String>,
+    #[validate(length(max = 500, message = "Máximo 500 caracteres"), custom(function = "validation::video_url"))]
     pub video_embed_url: Option<String>,
+    #[validate(custom(function = "validation::article_status"))]
     pub status: Option<String>,
     pub is_featured: Option<bool>,
     pub is_breaking: Option<bool>,
     pub published_at: Option<DateTime<Utc>>,
+    #[validate(length(max = "MAX_CONTRIBUTORS", message = "Demasiados créditos (máx. 20)"), nested)]
+    pub contributors: Option<Vec<ContributorInput>>, // Si viene, reemplaza todos los créditos
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct FilterOptions {
     pub category_id: Option<i32>,
     pub search: Option<String>, // <--- NUEVO CAMPO DE BÚSQUEDA
@@ -36,19 +65,10 @@ pub struct FilterOptions {
 
 // GET /api/articles (Soporta ?category_id=1&search=texto)
 pub async fn list_articles_handler(
-    opts: Option<Query<FilterOptions>>,
+    // Sin query string todos los filtros quedan en None; uno mal formado es 400
+    ApiQuery(opts): ApiQuery<FilterOptions>,
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
-    // Extraemos los valores o los dejamos en None
-    let Query(opts) = opts.unwrap_or(Query(FilterOptions { 
-        category_id: None, 
-        search: None,
-        is_featured: None,
-        is_breaking: None,
-        has_video: None,
-        tag_id: None,
-    }));
-
+) -> Result<impl IntoResponse, ApiError> {
     let category_id = opts.category_id;
     let search_term = opts.search;
     let is_featured = opts.is_featured;
@@ -78,8 +98,11 @@ pub async fn list_articles_handler(
             is_featured as "is_featured!: bool", 
             is_breaking as "is_breaking!: bool", 
             views_count as "views_count!: i64",
-            published_at, created_at, updated_at 
-        FROM articles 
+            published_at, created_at, updated_at,
+            b.author as "author: sqlx::types::Json<AuthorSummary>",
+            b.contributors as "contributors!: sqlx::types::Json<Vec<Contributor>>"
+        FROM articles
+        JOIN article_bylines b ON b.article_id = articles.id
         WHERE 
             ($1::int IS NULL OR category_id = $1)
             AND
@@ -109,137 +132,136 @@ pub async fn list_articles_handler(
         tag_id
     )
     .fetch_all(&pool)
-    .await;
+    .await
+    .map_err(ApiError::db("Error buscando noticias"))?;
 
-    match result {
-        Ok(data) => (StatusCode::OK, Json(data)).into_response(),
-        Err(e) => {
-            tracing::error!("Error buscando noticias: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error de base de datos").into_response()
-        }
-    }
+    Ok((StatusCode::OK, Json(result)))
 }
 
 // GET /api/articles/most-read
 pub async fn most_read_handler(
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
-    let result = sqlx::query_as!(
-        Article,
-        r#"
-        SELECT 
-            id, title, slug, content, excerpt, main_image_url, video_embed_url,
-            author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
-            is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
-            published_at, created_at, updated_at
-        FROM articles
-        ORDER BY views_count DESC
-        LIMIT 10
-        "#
-    )
-    .fetch_all(&pool)
-    .await;
-
-    match result {
-        Ok(rows) => (StatusCode::OK, Json(rows)).into_response(),
-        Err(e) => {
-            tracing::error!("Error consultando más leídas: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    State(cache): State<ResponseCache>,
+) -> Result<impl IntoResponse, ApiError> {
+    cache
+        .get_or_load(CacheKey::MostRead, || async move {
+            sqlx::query_as!(
+                Article,
+                r#"
+                SELECT 
+                    id, title, slug, content, excerpt, main_image_url, video_embed_url,
+                    author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
+                    is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
+                    published_at, created_at, updated_at,
+                    b.author as "author: sqlx::types::Json<AuthorSummary>",
+                    b.contributors as "contributors!: sqlx::types::Json<Vec<Contributor>>"
+                FROM articles
+                JOIN article_bylines b ON b.article_id = articles.id
+                ORDER BY views_count DESC
+                LIMIT 10
+                "#
+            )
+            .fetch_all(&pool)
+            .await
+            .map_err(ApiError::db("Error consultando más leídas"))
+        })
+        .await
 }
 
 pub async fn featured_handler(
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
-    let result = sqlx::query_as!(
-        Article,
-        r#"
-        SELECT 
-            id, title, slug, content, excerpt, main_image_url, video_embed_url,
-            author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
-            is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
-            published_at, created_at, updated_at
-        FROM articles
-        WHERE is_featured = TRUE
-        ORDER BY published_at DESC NULLS LAST, created_at DESC
-        LIMIT 10
-        "#
-    )
-    .fetch_all(&pool)
-    .await;
-
-    match result {
-        Ok(rows) => (StatusCode::OK, Json(rows)).into_response(),
-        Err(e) => {
-            tracing::error!("Error consultando destacadas: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    State(cache): State<ResponseCache>,
+) -> Result<impl IntoResponse, ApiError> {
+    cache
+        .get_or_load(CacheKey::Featured, || async move {
+            sqlx::query_as!(
+                Article,
+                r#"
+                SELECT 
+                    id, title, slug, content, excerpt, main_image_url, video_embed_url,
+                    author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
+                    is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
+                    published_at, created_at, updated_at,
+                    b.author as "author: sqlx::types::Json<AuthorSummary>",
+                    b.contributors as "contributors!: sqlx::types::Json<Vec<Contributor>>"
+                FROM articles
+                JOIN article_bylines b ON b.article_id = articles.id
+                WHERE is_featured = TRUE
+                ORDER BY published_at DESC NULLS LAST, created_at DESC
+                LIMIT 10
+                "#
+            )
+            .fetch_all(&pool)
+            .await
+            .map_err(ApiError::db("Error consultando destacadas"))
+        })
+        .await
 }
 
 pub async fn breaking_handler(
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
-    let result = sqlx::query_as!(
-        Article,
-        r#"
-        SELECT 
-            id, title, slug, content, excerpt, main_image_url, video_embed_url,
-            author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
-            is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
-            published_at, created_at, updated_at
-        FROM articles
-        WHERE is_breaking = TRUE
-        ORDER BY published_at DESC NULLS LAST, updated_at DESC
-        LIMIT 10
-        "#
-    )
-    .fetch_all(&pool)
-    .await;
-
-    match result {
-        Ok(rows) => (StatusCode::OK, Json(rows)).into_response(),
-        Err(e) => {
-            tracing::error!("Error consultando breaking: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    State(cache): State<ResponseCache>,
+) -> Result<impl IntoResponse, ApiError> {
+    cache
+        .get_or_load(CacheKey::Breaking, || async move {
+            sqlx::query_as!(
+                Article,
+                r#"
+                SELECT 
+                    id, title, slug, content, excerpt, main_image_url, video_embed_url,
+                    author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
+                    is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
+                    published_at, created_at, updated_at,
+                    b.author as "author: sqlx::types::Json<AuthorSummary>",
+                    b.contributors as "contributors!: sqlx::types::Json<Vec<Contributor>>"
+                FROM articles
+                JOIN article_bylines b ON b.article_id = articles.id
+                WHERE is_breaking = TRUE
+                ORDER BY published_at DESC NULLS LAST, updated_at DESC
+                LIMIT 10
+                "#
+            )
+            .fetch_all(&pool)
+            .await
+            .map_err(ApiError::db("Error consultando breaking"))
+        })
+        .await
 }
 
 pub async fn videos_handler(
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
-    let result = sqlx::query_as!(
-        Article,
-        r#"
-        SELECT 
-            id, title, slug, content, excerpt, main_image_url, video_embed_url,
-            author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
-            is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
-            published_at, created_at, updated_at
-        FROM articles
-        WHERE video_embed_url IS NOT NULL
-        ORDER BY published_at DESC NULLS LAST, created_at DESC
-        LIMIT 10
-        "#
-    )
-    .fetch_all(&pool)
-    .await;
-
-    match result {
-        Ok(rows) => (StatusCode::OK, Json(rows)).into_response(),
-        Err(e) => {
-            tracing::error!("Error consultando videos: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    State(cache): State<ResponseCache>,
+) -> Result<impl IntoResponse, ApiError> {
+    cache
+        .get_or_load(CacheKey::Videos, || async move {
+            sqlx::query_as!(
+                Article,
+                r#"
+                SELECT 
+                    id, title, slug, content, excerpt, main_image_url, video_embed_url,
+                    author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
+                    is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
+                    published_at, created_at, updated_at,
+                    b.author as "author: sqlx::types::Json<AuthorSummary>",
+                    b.contributors as "contributors!: sqlx::types::Json<Vec<Contributor>>"
+                FROM articles
+                JOIN article_bylines b ON b.article_id = articles.id
+                WHERE video_embed_url IS NOT NULL
+                ORDER BY published_at DESC NULLS LAST, created_at DESC
+                LIMIT 10
+                "#
+            )
+            .fetch_all(&pool)
+            .await
+            .map_err(ApiError::db("Error consultando videos"))
+        })
+        .await
 }
 
 pub async fn related_handler(
-    Path(slug): Path<String>,
+    ApiPath(slug): ApiPath<String>,
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, ApiError> {
     // Obtener artículo base
     let base = sqlx::query!(
         r#"SELECT id, category_id FROM articles WHERE slug = $1"#,
@@ -247,12 +269,8 @@ pub async fn related_handler(
     )
     .fetch_optional(&pool)
     .await
-    .unwrap_or(None);
-
-    let base = match base {
-        Some(row) => row,
-        None => return (StatusCode::NOT_FOUND, "Noticia no encontrada").into_response(),
-    };
+    .map_err(ApiError::db("Error buscando noticia"))?
+    .ok_or_else(article_not_found)?;
 
     // Relacionados por categoría o tags compartidos
     let result = sqlx::query_as!(
@@ -262,8 +280,11 @@ pub async fn related_handler(
             a.id, a.title, a.slug, a.content, a.excerpt, a.main_image_url, a.video_embed_url,
             a.author_id, a.category_id, a.status as "status!: String", a.is_featured as "is_featured!: bool",
             a.is_breaking as "is_breaking!: bool", a.views_count as "views_count!: i64",
-            a.published_at, a.created_at, a.updated_at
+            a.published_at, a.created_at, a.updated_at,
+            b.author as "author: sqlx::types::Json<AuthorSummary>",
+            b.contributors as "contributors!: sqlx::types::Json<Vec<Contributor>>"
         FROM articles a
+        JOIN article_bylines b ON b.article_id = a.id
         WHERE a.id <> $1
           AND (
               (a.category_id IS NOT NULL AND a.category_id = $2)
@@ -280,23 +301,18 @@ pub async fn related_handler(
         base.category_id
     )
     .fetch_all(&pool)
-    .await;
+    .await
+    .map_err(ApiError::db("Error consultando relacionados"))?;
 
-    match result {
-        Ok(rows) => (StatusCode::OK, Json(rows)).into_response(),
-        Err(e) => {
-            tracing::error!("Error consultando relacionados: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    Ok((StatusCode::OK, Json(result)))
 }
 
 // POST /api/articles/:slug/view - incrementar vistas
 pub async fn increment_views_handler(
-    Path(slug): Path<String>,
+    ApiPath(slug): ApiPath<String>,
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
-    let result = sqlx::query!(
+) -> Result<impl IntoResponse, ApiError> {
+    let row = sqlx::query!(
         r#"
         UPDATE articles 
         SET views_count = views_count + 1 
@@ -306,56 +322,52 @@ pub async fn increment_views_handler(
         slug
     )
     .fetch_optional(&pool)
-    .await;
+    .await
+    .map_err(ApiError::db("Error incrementando vistas"))?
+    .ok_or_else(article_not_found)?;
+    metrics::record_article_view();
 
-    match result {
-        Ok(Some(row)) => (StatusCode::OK, Json(serde_json::json!({ "views_count": row.views_count }))).into_response(),
-        Ok(None) => (StatusCode::NOT_FOUND, "Noticia no encontrada").into_response(),
-        Err(e) => {
-            tracing::error!("Error incrementando vistas: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    Ok((StatusCode::OK, Json(serde_json::json!({ "views_count": row.views_count }))))
 }
 
 // POST /api/articles - Crear noticia (IGUAL QUE ANTES)
 pub async fn create_article_handler(
     State(pool): State<DbPool>,
+    State(cache): State<ResponseCache>,
+    ClientIp(ip): ClientIp,
     Extension(claims): Extension<Claims>,
-    Json(body): Json<CreateArticleSchema>,
-) -> impl IntoResponse {
+    ValidJson(body): ValidJson<CreateArticleSchema>,
+) -> Result<impl IntoResponse, ApiError> {
     let mut slug = slugify(&body.title);
     if slug.is_empty() {
         slug = format!("article-{}", Uuid::new_v4().simple());
     }
 
     let status = body.status.unwrap_or_else(|| "draft".to_string());
+    // Colaboradores: solo borradores
+    if status != "draft" {
+        permissions::check(&claims, Permission::ArticlesPublish)?;
+    }
     let is_featured = body.is_featured.unwrap_or(false);
     let is_breaking = body.is_breaking.unwrap_or(false);
 
-    let query_result = sqlx::query_as!(
-        Article,
+    // Sin créditos explícitos, quien la crea firma como autor
+    let contributors = body.contributors.unwrap_or_else(|| {
+        vec![ContributorInput { user_id: Some(claims.user_id), name: None, role: ContributorRole::Author }]
+    });
+    check_references(&pool, body.category_id, Some(&contributors)).await?;
+
+    let mut tx = pool.begin().await.map_err(ApiError::db("Error iniciando transacción"))?;
+
+    // Slug repetido -> 409; categoría inexistente -> 422
+    let id = sqlx::query_scalar!(
         r#"
         INSERT INTO articles (
             title, slug, content, excerpt, main_image_url, video_embed_url,
             author_id, category_id, status, is_featured, is_breaking, published_at
         ) 
          VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) 
-         RETURNING 
-            id, 
-            title, 
-            slug, 
-            content, 
-            excerpt, 
-            main_image_url, 
-            video_embed_url,
-            author_id, 
-            category_id, 
-            status as "status!: String", 
-            is_featured as "is_featured!: bool", 
-            is_breaking as "is_breaking!: bool", 
-            views_count as "views_count!: i64",
-            published_at, created_at, updated_at
+         RETURNING id
         "#,
         body.title,
         slug,
@@ -370,86 +382,119 @@ pub async fn create_article_handler(
         is_breaking,
         body.published_at
     )
-    .fetch_one(&pool)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(ApiError::db("Error al crear noticia"))?;
+
+    set_contributors(&mut tx, id, &contributors).await?;
+
+    let article = find_article(&mut tx, id).await.map_err(ApiError::db("Error al crear noticia"))?;
+    tx.commit().await.map_err(ApiError::db("Error al crear noticia"))?;
+    cache.invalidate(&CacheKey::ARTICLE_LISTS);
+
+    tracing::info!(
+        "article_created id={} author_id={} api_key_id={:?}",
+        article.id,
+        claims.user_id,
+        claims.api_key_id
+    );
+    audit::record(
+        &pool,
+        AuditEntry::new("article.create", "article", article.id)
+            .actor(&claims)
+            .ip(&ip)
+            .after(&article),
+    )
     .await;
-
-    match query_result {
-        Ok(article) => {
-            tracing::info!("article_created id={} author_id={}", article.id, claims.user_id);
-            (StatusCode::CREATED, Json(article)).into_response()
-        }
-        Err(e) => {
-            tracing::error!("Error al crear noticia: {:?}", e);
-            (StatusCode::BAD_REQUEST, "No se pudo crear la noticia").into_response()
-        }
-    }
+    Ok((StatusCode::CREATED, Json(article)))
 }
 
 // --- NUEVO: DELETE /api/articles/:id ---
 pub async fn delete_article_handler(
-    Path(id): Path<i64>, // Extraemos el ID de la URL
+    ApiPath(id): ApiPath<i64>, // Extraemos el ID de la URL
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
+    State(cache): State<ResponseCache>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut tx = pool.begin().await.map_err(ApiError::db("Error iniciando transacción"))?;
+
+    // Copia completa para la auditoría antes de borrar (créditos y tags caen en cascada)
+    let article = find_article(&mut tx, id).await.map_err(|e| match e {
+        sqlx::Error::RowNotFound => article_not_found(),
+        e => ApiError::db("Error buscando noticia")(e),
+    })?;
+
     // query! (con signo de admiración) verifica el SQL pero no devuelve filas mapeadas
-    let result = sqlx::query!("DELETE FROM articles WHERE id = $1", id)
-        .execute(&pool)
-        .await;
-
-    match result {
-        Ok(res) => {
-            // rows_affected nos dice si realmente borró algo
-            if res.rows_affected() == 0 {
-                (StatusCode::NOT_FOUND, "Noticia no encontrada").into_response()
-            } else {
-                tracing::info!("article_deleted id={}", id);
-                (StatusCode::OK, "Noticia eliminada correctamente").into_response()
-            }
-        }
-        Err(e) => {
-            tracing::error!("Error eliminando noticia: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    sqlx::query!("DELETE FROM articles WHERE id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(ApiError::db("Error eliminando noticia"))?;
+    tx.commit().await.map_err(ApiError::db("Error eliminando noticia"))?;
+    cache.invalidate(&CacheKey::ARTICLE_LISTS);
+
+    tracing::info!("article_deleted id={}", id);
+    audit::record(
+        &pool,
+        AuditEntry::new("article.delete", "article", id)
+            .actor(&claims)
+            .ip(&ip)
+            .before(&article),
+    )
+    .await;
+    Ok((StatusCode::OK, "Noticia eliminada correctamente"))
 }
 
 // --- NUEVO: PUT /api/articles/:id ---
 pub async fn update_article_handler(
-    Path(id): Path<i64>,
+    ApiPath(id): ApiPath<i64>,
     State(pool): State<DbPool>,
+    State(cache): State<ResponseCache>,
+    ClientIp(ip): ClientIp,
     Extension(claims): Extension<Claims>,
-    Json(body): Json<UpdateArticleSchema>,
-) -> impl IntoResponse {
+    ValidJson(body): ValidJson<UpdateArticleSchema>,
+) -> Result<impl IntoResponse, ApiError> {
     // Verificamos si existe primero para no dar falsos positivos
-    let existing = match sqlx::query!(
-        "SELECT id, author_id FROM articles WHERE id = $1",
-        id
+    let existing = sqlx::query!(
+        r#"
+        SELECT id, author_id, status as "status!: String",
+               EXISTS (
+                   SELECT 1 FROM article_contributors
+                   WHERE article_id = $1 AND user_id = $2 AND credit_role = 'author'
+               ) as "is_credited_author!"
+        FROM articles WHERE id = $1
+        "#,
+        id,
+        claims.user_id
     )
     .fetch_optional(&pool)
-    .await {
-        Ok(row) => row,
-        Err(e) => {
-            tracing::error!("Error buscando noticia {}: {:?}", id, e);
-            return (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response();
-        }
-    };
+    .await
+    .map_err(ApiError::db("Error buscando noticia"))?
+    .ok_or_else(article_not_found)?;
+
+    // Autorización: articles.edit_any edita cualquiera; articles.edit_own solo las propias
+    // (las que creó o en las que figura acreditado como autor)
+    let is_owner = existing.author_id == Some(claims.user_id) || existing.is_credited_author;
+    let needed = if is_owner { Permission::ArticlesEditOwn } else { Permission::ArticlesEditAny };
+    permissions::check(&claims, needed)?;
+
+    // Sin articles.publish solo se tocan borradores y no se puede cambiar su estado
+    let touches_published = existing.status != "draft"
+        || body.status.as_deref().is_some_and(|s| s != "draft");
+    if touches_published {
+        permissions::check(&claims, Permission::ArticlesPublish)?;
+    }
 
-    let existing = match existing {
-        Some(row) => row,
-        None => return (StatusCode::NOT_FOUND, "Noticia no encontrada").into_response(),
-    };
+    check_references(&pool, body.category_id, body.contributors.as_deref()).await?;
 
-    // Autorización: admin puede todo, editor solo sus artículos
-    let is_admin = claims.role == "admin";
-    let is_owner = existing.author_id == Some(claims.user_id);
+    let mut tx = pool.begin().await.map_err(ApiError::db("Error iniciando transacción"))?;
 
-    if !is_admin && !is_owner {
-        return (StatusCode::FORBIDDEN, "No puedes editar noticias de otros").into_response();
-    }
+    // Estado previo para la auditoría
+    let before = find_article(&mut tx, id).await.map_err(ApiError::db("Error buscando noticia"))?;
 
     // Truco SQL: COALESCE($1, title) significa:
     // "Si el valor $1 que me envían es NULL, deja el 'title' que ya estaba en la base de datos".
-    let result = sqlx::query_as!(
-        Article,
+    sqlx::query!(
         r#"
          UPDATE articles SET 
             title = COALESCE($1, title),
@@ -464,21 +509,6 @@ pub async fn update_article_handler(
             published_at = COALESCE($10, published_at),
             updated_at = NOW() 
          WHERE id = $11
-         RETURNING 
-            id, 
-            title, 
-            slug, 
-            content, 
-            excerpt, 
-            main_image_url, 
-            video_embed_url,
-            author_id, 
-            category_id, 
-            status as "status!: String", 
-            is_featured as "is_featured!: bool", 
-            is_breaking as "is_breaking!: bool", 
-            views_count as "views_count!: i64",
-            published_at, created_at, updated_at
         "#,
         body.title,
         body.content,
@@ -492,27 +522,146 @@ pub async fn update_article_handler(
         body.published_at,
         id
     )
-    .fetch_one(&pool)
+    .execute(&mut *tx)
+    .await
+    .map_err(ApiError::db("Error actualizando noticia"))?;
+
+    // Créditos: si vienen, reemplazan el set completo (como los tags)
+    if let Some(contributors) = &body.contributors {
+        set_contributors(&mut tx, id, contributors).await?;
+    }
+
+    let updated_article = find_article(&mut tx, id).await.map_err(ApiError::db("Error actualizando noticia"))?;
+    tx.commit().await.map_err(ApiError::db("Error actualizando noticia"))?;
+    cache.invalidate(&CacheKey::ARTICLE_LISTS);
+
+    tracing::info!("article_updated id={} by_user={}", updated_article.id, claims.user_id);
+    audit::record(
+        &pool,
+        AuditEntry::new("article.update", "article", id)
+            .actor(&claims)
+            .ip(&ip)
+            .before(&before)
+            .after(&updated_article),
+    )
     .await;
+    Ok((StatusCode::OK, Json(updated_article)))
+}
 
-    match result {
-        Ok(updated_article) => {
-            tracing::info!("article_updated id={} by_user={}", updated_article.id, claims.user_id);
-            (StatusCode::OK, Json(updated_article)).into_response()
+fn article_not_found() -> ApiError {
+    ApiError::not_found("Noticia no encontrada")
+}
+
+// Lo que el schema no puede comprobar: que la categoría y los usuarios acreditados existan.
+// Se hace antes de abrir la transacción para devolver 422 por campo (la FK sigue de respaldo).
+async fn check_references(
+    pool: &DbPool,
+    category_id: Option<i32>,
+    contributors: Option<&[ContributorInput]>,
+) -> Result<(), ApiError> {
+    let mut fields = Map::new();
+
+    if let Some(category_id) = category_id {
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM categories WHERE id = $1) as "exists!""#,
+            category_id
+        )
+        .fetch_one(pool)
+        .await
+        .map_err(ApiError::db("Error comprobando categoría"))?;
+        if !exists {
+            fields.insert("category_id".into(), json!([{ "code": "not_found", "message": "La categoría no existe" }]));
         }
-        Err(e) => {
-            tracing::error!("Error actualizando noticia: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error al actualizar").into_response()
+    }
+
+    let contributors = contributors.unwrap_or_default();
+    let user_ids: Vec<i64> = contributors.iter().filter_map(|c| c.user_id).collect();
+    if !user_ids.is_empty() {
+        let found = sqlx::query_scalar!("SELECT id FROM users WHERE id = ANY($1)", &user_ids)
+            .fetch_all(pool)
+            .await
+            .map_err(ApiError::db("Error comprobando créditos"))?;
+        for (i, c) in contributors.iter().enumerate() {
+            if let Some(user_id) = c.user_id
+                && !found.contains(&user_id)
+            {
+                fields.insert(
+                    format!("contributors[{}].user_id", i),
+                    json!([{ "code": "not_found", "message": "El usuario no existe" }]),
+                );
+            }
         }
     }
+
+    if fields.is_empty() { Ok(()) } else { Err(ApiError::invalid_fields(fields)) }
+}
+
+// Reemplaza los créditos de una noticia; el orden del array es el orden de la firma
+async fn set_contributors(
+    tx: &mut Transaction<'_, Postgres>,
+    article_id: i64,
+    contributors: &[ContributorInput],
+) -> Result<(), ApiError> {
+    sqlx::query!("DELETE FROM article_contributors WHERE article_id = $1", article_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(ApiError::db("Error guardando créditos"))?;
+
+    let user_ids: Vec<Option<i64>> = contributors.iter().map(|c| c.user_id).collect();
+    let names: Vec<Option<String>> = contributors
+        .iter()
+        .map(|c| c.name.as_ref().map(|n| n.trim().to_string()))
+        .collect();
+    let roles: Vec<String> = contributors.iter().map(|c| c.role.as_str().to_string()).collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO article_contributors (article_id, user_id, external_name, credit_role, position)
+        SELECT $1, c.user_id, c.external_name, c.credit_role, (c.ord - 1)::int
+        FROM UNNEST($2::bigint[], $3::text[], $4::text[]) WITH ORDINALITY
+             AS c(user_id, external_name, credit_role, ord)
+        "#,
+        article_id,
+        &user_ids as &[Option<i64>],
+        &names as &[Option<String>],
+        &roles
+    )
+    .execute(&mut **tx)
+    .await
+    // user_id inexistente -> 422
+    .map_err(ApiError::db("Error guardando créditos"))?;
+
+    Ok(())
+}
+
+// Noticia completa (byline y créditos incluidos) tras crearla o editarla
+async fn find_article(tx: &mut Transaction<'_, Postgres>, id: i64) -> Result<Article, sqlx::Error> {
+    sqlx::query_as!(
+        Article,
+        r#"
+        SELECT
+            id, title, slug, content, excerpt, main_image_url, video_embed_url,
+            author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
+            is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
+            published_at, created_at, updated_at,
+            b.author as "author: sqlx::types::Json<AuthorSummary>",
+            b.contributors as "contributors!: sqlx::types::Json<Vec<Contributor>>"
+        FROM articles
+        JOIN article_bylines b ON b.article_id = articles.id
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_one(&mut **tx)
+    .await
 }
 
 // GET /api/articles/:slug - Leer una noticia individual
 pub async fn get_article_handler(
-    Path(slug): Path<String>, // Leemos el slug (ej: "robo-en-centro")
+    ApiPath(slug): ApiPath<String>, // Leemos el slug (ej: "robo-en-centro")
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
-    let result = sqlx::query_as!(
+) -> Result<impl IntoResponse, ApiError> {
+    let article = sqlx::query_as!(
         Article,
         r#"
          SELECT 
@@ -529,45 +678,19 @@ pub async fn get_article_handler(
             is_featured as "is_featured!: bool", 
             is_breaking as "is_breaking!: bool", 
             views_count as "views_count!: i64",
-             published_at, created_at, updated_at 
-         FROM articles 
+             published_at, created_at, updated_at,
+             b.author as "author: sqlx::types::Json<AuthorSummary>",
+             b.contributors as "contributors!: sqlx::types::Json<Vec<Contributor>>"
+         FROM articles
+         JOIN article_bylines b ON b.article_id = articles.id
          WHERE slug = $1
         "#,
         slug
     )
     .fetch_optional(&pool) // fetch_optional devuelve Option<Article> (puede ser None)
-    .await;
-
-    match result {
-        Ok(Some(article)) => (StatusCode::OK, Json(article)).into_response(),
-        Ok(None) => (StatusCode::NOT_FOUND, "Noticia no encontrada").into_response(),
-        Err(e) => {
-            tracing::error!("Error buscando noticia {}: {:?}", slug, e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
-}
-
-fn slugify(input: &str) -> String {
-    let mut slug = String::with_capacity(input.len());
-    let mut prev_hyphen = false;
-
-    for ch in input.to_lowercase().chars() {
-        if ch.is_ascii_alphanumeric() {
-            slug.push(ch);
-            prev_hyphen = false;
-        } else if !prev_hyphen {
-            slug.push('-');
-            prev_hyphen = true;
-        }
-    }
-
-    while slug.ends_with('-') {
-        slug.pop();
-    }
-    while slug.starts_with('-') {
-        slug.remove(0);
-    }
+    .await
+    .map_err(ApiError::db("Error buscando noticia"))?
+    .ok_or_else(article_not_found)?;
 
-    slug
+    Ok((StatusCode::OK, Json(article)))
 }
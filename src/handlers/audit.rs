@@ -0,0 +1,52 @@
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiQuery},
+    models::audit::{AuditLogEntry, AuditPage, AuditQuery},
+    utils::pagination::{self, Page},
+};
+
+const MAX_PER_PAGE: i64 = 200;
+
+// GET /api/admin/audit (audit.read) - ?actor_id=&action=&entity_type=&entity_id=&from=&to=&page=&per_page=
+// Más reciente primero; from/to en RFC 3339
+pub async fn list_audit_handler(
+    ApiQuery(query): ApiQuery<AuditQuery>,
+    State(pool): State<DbPool>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Page { page, per_page, offset } = pagination::page(query.page, query.per_page, 50, MAX_PER_PAGE)?;
+
+    let entries = sqlx::query_as!(
+        AuditLogEntry,
+        r#"
+        SELECT id, actor_id, actor_email, api_key_id, action, entity_type, entity_id,
+               before, after, ip_address, created_at
+        FROM audit_log
+        WHERE ($1::bigint IS NULL OR actor_id = $1)
+          AND ($2::text IS NULL OR action = $2)
+          AND ($3::text IS NULL OR entity_type = $3)
+          AND ($4::text IS NULL OR entity_id = $4)
+          AND ($5::timestamptz IS NULL OR created_at >= $5)
+          AND ($6::timestamptz IS NULL OR created_at < $6)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $7 OFFSET $8
+        "#,
+        query.actor_id,
+        query.action,
+        query.entity_type,
+        query.entity_id,
+        query.from,
+        query.to,
+        per_page,
+        offset
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(ApiError::db("Error consultando auditoría"))?;
+
+    Ok((StatusCode::OK, Json(AuditPage { entries, page, per_page })))
+}
@@ -1,151 +1,271 @@
 use axum::{
     extract::{State, Json},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
 };
+use std::sync::Arc;
 use axum_extra::headers::{Authorization, authorization::Bearer};
 use axum_extra::TypedHeader;
-use jsonwebtoken::{encode, EncodingKey, Header};
 use chrono::{Utc, Duration};
+use uuid::Uuid;
 use crate::{
+    config::{AppConfig, AuthConfig},
     db::DbPool,
-    models::user::{User, LoginPayload, RegisterPayload, AuthResponse, Claims},
-    utils::security::{hash_password, verify_password},
+    error::{ApiError, ApiJson, ValidJson},
+    handlers::two_factor,
+    models::{
+        role::{Permission, Role},
+        user::{User, LoginPayload, RegisterPayload, AuthResponse, Claims},
+    },
+    utils::{
+        audit::{self, AuditEntry},
+        bootstrap::{self, SetupToken, SETUP_TOKEN_HEADER},
+        jwt::{encode_token, session_claims},
+        jwt_keys::JwtKeys,
+        login_guard,
+        metrics,
+        permissions,
+        request_info::{ClientInfo, ClientIp},
+        security::{hash_password, verify_password},
+        slug::slugify,
+    },
 };
 
-// POST /api/auth/register (Solo admins; primer usuario se permite sin token y queda como admin)
+// POST /api/auth/register (permiso users.manage). Sin ningún admin, el primero se crea sin JWT
+// pero con el header X-Setup-Token (ver utils::bootstrap) o desde la CLI (`create-admin`)
 pub async fn register_handler(
     State(pool): State<DbPool>,
-    // Token opcional: si ya existe un usuario, exigimos que sea admin
+    State(setup_token): State<SetupToken>,
+    State(keys): State<Arc<JwtKeys>>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
     maybe_auth: Option<TypedHeader<Authorization<Bearer>>>,
-    Json(payload): Json<RegisterPayload>,
-) -> impl IntoResponse {
-    // Contamos usuarios existentes para decidir si es bootstrap
-    let user_count = match sqlx::query_scalar!("SELECT COUNT(*) FROM users")
-        .fetch_one(&pool)
-        .await
-    {
-        Ok(count) => count.unwrap_or(0),
-        Err(e) => {
-            tracing::error!("Error contando usuarios: {:?}", e);
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    ValidJson(payload): ValidJson<RegisterPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Con JWT: alta normal, exige users.manage. Sin JWT: solo el bootstrap con token
+    let actor = match maybe_auth {
+        Some(TypedHeader(auth_header)) => {
+            let claims = session_claims(&pool, &keys, auth_header.token()).await?;
+            permissions::check(&claims, Permission::UsersManage)?;
+            Some(claims)
         }
-    };
-
-    // Si ya hay usuarios, exigimos token admin
-    if user_count > 0 {
-        let TypedHeader(auth_header) = match maybe_auth {
-            Some(h) => h,
-            None => return (StatusCode::FORBIDDEN, "Solo un admin puede crear usuarios").into_response(),
-        };
-
-        let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET debe estar en .env");
-        let token = auth_header.token();
-        let validation = jsonwebtoken::Validation::default();
-        let token_data = jsonwebtoken::decode::<Claims>(
-            token,
-            &jsonwebtoken::DecodingKey::from_secret(secret.as_bytes()),
-            &validation,
-        );
-
-        match token_data {
-            Ok(data) => {
-                if data.claims.role != "admin" {
-                    return (StatusCode::FORBIDDEN, "Solo un admin puede crear usuarios").into_response();
+        None => {
+            let candidate = headers.get(SETUP_TOKEN_HEADER).and_then(|v| v.to_str().ok());
+            match candidate {
+                Some(candidate) if setup_token.matches(candidate) => None,
+                Some(_) => {
+                    tracing::warn!("bootstrap_rejected reason=invalid_token ip={}", ip);
+                    return Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid_setup_token", "Token de instalación inválido"));
                 }
+                None => return Err(ApiError::missing_permission(Permission::UsersManage)),
             }
-            Err(_) => return StatusCode::UNAUTHORIZED.into_response(),
         }
-    }
+    };
+    let bootstrap = actor.is_none();
 
     // 1. Hashear la contraseña (nunca guardarla plana)
-    let hashed_password = match hash_password(&payload.password) {
-        Ok(h) => h,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Error de seguridad").into_response(),
-    };
+    let hashed_password = hash_password(&payload.password).map_err(|_| ApiError::internal())?;
+
+    // Rol: el del bootstrap es admin; el resto, el pedido o editor
+    let role = if bootstrap { Role::Admin } else { payload.role.unwrap_or(Role::Editor) };
 
-    // Rol: el primer usuario se vuelve admin automáticamente; el resto, editor
-    let role = if user_count == 0 { "admin" } else { "editor" };
+    // 2. Insertar en Base de Datos. El bootstrap va en una transacción SERIALIZABLE: de dos
+    //    peticiones simultáneas solo una ve "no hay admin" y confirma; la otra recibe 409
+    let mut tx = pool.begin().await.map_err(ApiError::db("Error iniciando transacción"))?;
+    if bootstrap {
+        sqlx::query!("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE")
+            .execute(&mut *tx)
+            .await
+            .map_err(ApiError::db("Error iniciando transacción"))?;
+        if bootstrap::admin_exists(&mut *tx).await.map_err(bootstrap_error)? {
+            return Err(already_initialized());
+        }
+    }
 
-    // 2. Insertar en Base de Datos
-    let result = sqlx::query_as!(
+    // Slug de autor inicial a partir del username (si ya está cogido, se elige en /api/me/profile)
+    let user = sqlx::query_as!(
         User,
-        "INSERT INTO users (username, email, password_hash, role) 
-         VALUES ($1, $2, $3, $4) 
-         RETURNING id, username, email, password_hash, role",
+        "INSERT INTO users (username, email, password_hash, role, author_slug) 
+         VALUES ($1, $2, $3, $4, (SELECT $5::text WHERE $5 <> '' AND NOT EXISTS (SELECT 1 FROM users WHERE author_slug = $5))) 
+         RETURNING id, username, email, password_hash, role, totp_enabled, totp_required",
         payload.username,
         payload.email,
         hashed_password,
-        role
+        role.as_str(),
+        slugify(&payload.username)
     )
-    .fetch_one(&pool)
-    .await;
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => ApiError::conflict("El usuario o email ya existe")
+            .with_details(serde_json::json!({ "constraint": db.constraint() })),
+        e => bootstrap_error(e),
+    })?;
+    tx.commit().await.map_err(bootstrap_error)?;
 
-    match result {
-        Ok(_) => (StatusCode::CREATED, "Usuario creado exitosamente").into_response(),
-        Err(e) => {
-            tracing::error!("Error creando usuario: {:?}", e);
-            // Probablemente el email ya existe
-            (StatusCode::CONFLICT, "El usuario o email ya existe").into_response()
-        }
+    let mut entry = AuditEntry::new("user.create", "user", user.id)
+        .ip(&ip)
+        .after(&user);
+    if let Some(claims) = &actor {
+        entry = entry.actor(claims);
+    } else {
+        tracing::info!("bootstrap_admin_created user_id={}", user.id);
+    }
+    audit::record(&pool, entry).await;
+    Ok((StatusCode::CREATED, "Usuario creado exitosamente"))
+}
+
+fn already_initialized() -> ApiError {
+    ApiError::new(StatusCode::CONFLICT, "already_initialized", "Ya existe un administrador")
+}
+
+// Un conflicto de serialización (40001) significa que otra petición creó el admin a la vez
+fn bootstrap_error(e: sqlx::Error) -> ApiError {
+    match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some("40001") => already_initialized(),
+        _ => ApiError::db("Error creando usuario")(e),
     }
 }
 
 // POST /api/auth/login
 pub async fn login_handler(
     State(pool): State<DbPool>,
-    Json(payload): Json<LoginPayload>,
-) -> impl IntoResponse {
+    State(config): State<Arc<AppConfig>>,
+    State(keys): State<Arc<JwtKeys>>,
+    device: ClientInfo,
+    ApiJson(payload): ApiJson<LoginPayload>,
+) -> Result<Response, ApiError> {
+    // 0. Cuenta o IP con demasiados fallos: respondemos igual que con credenciales malas
+    //    (no revelamos el bloqueo) y ni siquiera gastamos un hash Argon2
+    if login_guard::is_locked(&pool, &payload.email, &device.ip)
+        .await
+        .map_err(ApiError::db("Error consultando bloqueo de login"))?
+    {
+        metrics::record_login("password", "locked");
+        return Err(invalid_credentials());
+    }
+
     // 1. Buscar usuario por email
     let user = sqlx::query_as!(
         User,
-        "SELECT id, username, email, password_hash, role FROM users WHERE email = $1",
+        "SELECT id, username, email, password_hash, role, totp_enabled, totp_required FROM users WHERE email = $1",
         payload.email
     )
     .fetch_optional(&pool)
     .await
     .unwrap_or(None);
 
+    // 2. Verificar contraseña (Argon2)
     let user = match user {
-        Some(u) => u,
-        None => return (StatusCode::UNAUTHORIZED, "Credenciales inválidas").into_response(),
+        Some(u) if verify_password(&payload.password, &u.password_hash) => u,
+        other => {
+            metrics::record_login("password", "failure");
+            // Email inexistente cuenta igual que contraseña errónea
+            let user_id = other.map(|u| u.id);
+            let entity_id = user_id.map(|id| id.to_string()).unwrap_or_else(|| payload.email.clone());
+            audit::record(
+                &pool,
+                AuditEntry::new("auth.login_failed", "user", &entity_id)
+                    .login_actor(user_id, &payload.email)
+                    .ip(device.ip.clone()),
+            )
+            .await;
+            match login_guard::register_failure(&pool, &payload.email, &device.ip).await {
+                Ok(true) => {
+                    audit::record(
+                        &pool,
+                        AuditEntry::new("auth.lockout", "user", &entity_id)
+                            .login_actor(user_id, &payload.email)
+                            .ip(device.ip.clone()),
+                    )
+                    .await
+                }
+                Ok(false) => {}
+                Err(e) => tracing::error!("Error registrando intento fallido: {:?}", e),
+            }
+            return Err(invalid_credentials());
+        }
     };
 
-    // 2. Verificar contraseña (Argon2)
-    let is_valid = verify_password(&payload.password, &user.password_hash);
+    // 3. Segundo factor: con TOTP activo (u obligatorio y aún sin activar) el login
+    //    se completa en POST /api/auth/2fa/verify
+    if user.totp_enabled || user.totp_required {
+        metrics::record_login("password", "challenge");
+        return two_factor::challenge_response(&pool, &config.auth, &keys, &user).await;
+    }
 
-    if !is_valid {
-        return (StatusCode::UNAUTHORIZED, "Credenciales inválidas").into_response();
+    if let Err(e) = login_guard::clear_account(&pool, &user.email).await {
+        tracing::error!("Error limpiando intentos fallidos: {:?}", e);
     }
 
-    // 3. Generar JWT Token
-    // Calculamos expiración (ej: 24 horas desde ahora)
-    let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(24))
-        .expect("Fecha inválida")
-        .timestamp() as usize;
+    let role = user.role.parse::<Role>().map_err(|e| {
+        tracing::error!("Usuario {} con rol inválido: {}", user.id, e);
+        ApiError::internal()
+    })?;
+
+    // 4. Sesión + JWT
+    let token = start_session(&pool, &config.auth, &keys, user.id, &user.email, role, &device).await?;
+    metrics::record_login("password", "success");
+    audit::record(
+        &pool,
+        AuditEntry::new("auth.login", "user", user.id)
+            .login_actor(Some(user.id), &user.email)
+            .ip(device.ip),
+    )
+    .await;
+    Ok((StatusCode::OK, Json(AuthResponse {
+        token,
+        token_type: "Bearer".to_string(),
+        recovery_codes: None,
+    })).into_response())
+}
+
+// Mismo error para email inexistente, contraseña errónea o cuenta bloqueada
+pub fn invalid_credentials() -> ApiError {
+    ApiError::new(StatusCode::UNAUTHORIZED, "invalid_credentials", "Credenciales inválidas")
+}
+
+// Registra la sesión (dispositivo + IP) para poder listarla y revocarla, y firma el JWT que apunta a ella
+pub async fn start_session(
+    pool: &DbPool,
+    config: &AuthConfig,
+    keys: &JwtKeys,
+    user_id: i64,
+    email: &str,
+    role: Role,
+    device: &ClientInfo,
+) -> Result<String, ApiError> {
+    // Calculamos expiración (session_ttl_hours desde ahora, 24 por defecto)
+    let expires_at = Utc::now()
+        .checked_add_signed(Duration::hours(config.session_ttl_hours))
+        .expect("Fecha inválida");
+
+    let session_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        INSERT INTO user_sessions (id, user_id, user_agent, ip_address, expires_at)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+        session_id,
+        user_id,
+        device.user_agent,
+        device.ip,
+        expires_at
+    )
+    .execute(pool)
+    .await
+    .map_err(ApiError::db("Error creando sesión"))?;
 
     let claims = Claims {
-        sub: user.email.clone(),
-        exp: expiration,
+        sub: email.to_string(),
+        exp: expires_at.timestamp() as usize,
         iat: Utc::now().timestamp() as usize,
-        user_id: user.id,
-        role: user.role,
+        user_id,
+        role,
+        sid: session_id,
+        api_key_id: None,
+        scopes: None,
     };
 
-    let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET debe estar en .env");
-    
-    let token = encode(
-        &Header::default(), 
-        &claims, 
-        &EncodingKey::from_secret(secret.as_bytes())
-    ).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
-
-    match token {
-        Ok(t) => (StatusCode::OK, Json(AuthResponse { 
-            token: t,
-            token_type: "Bearer".to_string() 
-        })).into_response(),
-        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Error generando token").into_response(),
-    }
+    encode_token(keys, &claims)
 }
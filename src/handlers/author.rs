@@ -0,0 +1,155 @@
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+use std::collections::BTreeMap;
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiPath, ApiQuery, ValidJson},
+    models::{
+        article::{Article, Contributor},
+        author::{AuthorPage, AuthorPageQuery, AuthorProfile, AuthorSummary, UpdateProfilePayload},
+        user::Claims,
+    },
+    utils::{
+        cache::{CacheKey, ResponseCache},
+        pagination::{self, Page},
+    },
+};
+
+const MAX_PER_PAGE: i64 = 50;
+
+// GET /api/authors/:slug?page=1&per_page=20 - perfil público + noticias publicadas (propias o acreditadas)
+pub async fn get_author_handler(
+    ApiPath(slug): ApiPath<String>,
+    ApiQuery(query): ApiQuery<AuthorPageQuery>,
+    State(pool): State<DbPool>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Page { page, per_page, offset } = pagination::page(query.page, query.per_page, 20, MAX_PER_PAGE)?;
+
+    let author = sqlx::query_as!(
+        AuthorProfile,
+        r#"
+        SELECT id, author_slug as slug, COALESCE(display_name, username) as "display_name!",
+               bio, photo_url, social_links as "social_links: sqlx::types::Json<BTreeMap<String, String>>"
+        FROM users
+        WHERE author_slug = $1
+        "#,
+        slug
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(ApiError::db("Error buscando autor"))?
+    .ok_or_else(|| ApiError::not_found("Autor no encontrado"))?;
+
+    let total = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!" FROM articles
+        WHERE status = 'published'
+          AND (author_id = $1 OR EXISTS (
+              SELECT 1 FROM article_contributors ac WHERE ac.article_id = articles.id AND ac.user_id = $1
+          ))
+        "#,
+        author.id
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(ApiError::db("Error contando noticias del autor"))?;
+
+    let articles = sqlx::query_as!(
+        Article,
+        r#"
+        SELECT
+            id, title, slug, content, excerpt, main_image_url, video_embed_url,
+            author_id, category_id, status as "status!: String", is_featured as "is_featured!: bool",
+            is_breaking as "is_breaking!: bool", views_count as "views_count!: i64",
+            published_at, created_at, updated_at,
+            b.author as "author: sqlx::types::Json<AuthorSummary>",
+            b.contributors as "contributors!: sqlx::types::Json<Vec<Contributor>>"
+        FROM articles
+        JOIN article_bylines b ON b.article_id = articles.id
+        WHERE status = 'published'
+          AND (author_id = $1 OR EXISTS (
+              SELECT 1 FROM article_contributors ac WHERE ac.article_id = articles.id AND ac.user_id = $1
+          ))
+        ORDER BY published_at DESC NULLS LAST, created_at DESC
+        LIMIT $2 OFFSET $3
+        "#,
+        author.id,
+        per_page,
+        offset
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(ApiError::db("Error listando noticias del autor"))?;
+
+    Ok((StatusCode::OK, Json(AuthorPage { author, articles, page, per_page, total })))
+}
+
+// GET /api/me/profile - perfil público propio (lo que verán los lectores)
+pub async fn get_my_profile_handler(
+    State(pool): State<DbPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ApiError> {
+    let profile = sqlx::query_as!(
+        AuthorProfile,
+        r#"
+        SELECT id, author_slug as slug, COALESCE(display_name, username) as "display_name!",
+               bio, photo_url, social_links as "social_links: sqlx::types::Json<BTreeMap<String, String>>"
+        FROM users
+        WHERE id = $1
+        "#,
+        claims.user_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(ApiError::db("Error leyendo perfil"))?
+    .ok_or_else(|| ApiError::not_found("Usuario no encontrado"))?;
+
+    Ok((StatusCode::OK, Json(profile)))
+}
+
+// PUT /api/me/profile - editar perfil público propio (campos ausentes no se tocan)
+pub async fn update_my_profile_handler(
+    State(pool): State<DbPool>,
+    State(cache): State<ResponseCache>,
+    Extension(claims): Extension<Claims>,
+    ValidJson(body): ValidJson<UpdateProfilePayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let profile = sqlx::query_as!(
+        AuthorProfile,
+        r#"
+        UPDATE users SET
+            author_slug = COALESCE($2, author_slug),
+            display_name = COALESCE($3, display_name),
+            bio = COALESCE($4, bio),
+            photo_url = COALESCE($5, photo_url),
+            social_links = COALESCE($6, social_links)
+        WHERE id = $1
+        RETURNING id, author_slug as slug, COALESCE(display_name, username) as "display_name!",
+                  bio, photo_url, social_links as "social_links: sqlx::types::Json<BTreeMap<String, String>>"
+        "#,
+        claims.user_id,
+        body.slug,
+        body.display_name,
+        body.bio,
+        body.photo_url,
+        body.social_links.map(sqlx::types::Json) as _
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            ApiError::conflict("Ese slug ya lo usa otro autor").with_details(serde_json::json!({ "field": "slug" }))
+        }
+        e => ApiError::db("Error actualizando perfil")(e),
+    })?
+    .ok_or_else(|| ApiError::not_found("Usuario no encontrado"))?;
+
+    // Las listas en caché llevan los bylines (nombre, slug y foto del autor)
+    cache.invalidate(&CacheKey::ARTICLE_LISTS);
+    tracing::info!("author_profile_updated user_id={}", claims.user_id);
+    Ok((StatusCode::OK, Json(profile)))
+}
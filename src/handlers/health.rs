@@ -1,5 +1,156 @@
-use axum::http::StatusCode;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    path::Path,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+use crate::{
+    config::AppConfig,
+    db::{migrations, DbPool},
+    models::health::{CheckStatus, ComponentHealth, HealthReport, ReadinessResponse},
+};
 
+// Tiempo máximo por comprobación: un sondeo que se cuelga cuenta como fallo
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+static STARTED_AT: OnceLock<DateTime<Utc>> = OnceLock::new();
+
+// Lo llama serve al arrancar (para el uptime del informe)
+pub fn mark_started() {
+    STARTED_AT.get_or_init(Utc::now);
+}
+
+// GET /healthz - liveness: el proceso responde. No toca la base, para que una caída de Postgres
+// no haga que el orquestador reinicie en bucle instancias sanas
 pub async fn health_handler() -> StatusCode {
     StatusCode::OK
 }
+
+// GET /readyz - readiness: 200 si la instancia puede atender tráfico, 503 si no
+pub async fn readiness_handler(
+    State(pool): State<DbPool>,
+    State(config): State<Arc<AppConfig>>,
+) -> Response {
+    let components = run_checks(&pool, &config).await;
+    let ready = all_ok(&components);
+    if !ready {
+        let failed: Vec<String> = components
+            .iter()
+            .filter(|(_, c)| c.status != CheckStatus::Ok)
+            .map(|(name, c)| format!("{}: {}", name, c.error.as_deref().unwrap_or("?")))
+            .collect();
+        tracing::warn!("readiness_failed checks={:?}", failed);
+    }
+
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = ReadinessResponse {
+        status: if ready { "ready" } else { "not_ready" },
+        checks: components.iter().map(|(name, c)| (*name, c.status)).collect(),
+    };
+    (status, Json(body)).into_response()
+}
+
+// GET /api/admin/health - mismas comprobaciones con errores y detalles, más versión y uptime
+pub async fn health_report_handler(
+    State(pool): State<DbPool>,
+    State(config): State<Arc<AppConfig>>,
+) -> Json<HealthReport> {
+    let components = run_checks(&pool, &config).await;
+    let started_at = *STARTED_AT.get_or_init(Utc::now);
+    Json(HealthReport {
+        status: if all_ok(&components) { "ready" } else { "not_ready" },
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: env!("GIT_SHA"),
+        started_at,
+        uptime_secs: (Utc::now() - started_at).num_seconds(),
+        components,
+    })
+}
+
+async fn run_checks(pool: &DbPool, config: &AppConfig) -> BTreeMap<&'static str, ComponentHealth> {
+    // El pool se mira antes de que las otras comprobaciones ocupen conexiones
+    let pool_status = timed(async { check_pool(pool) }).await;
+    let (database, schema, uploads) = tokio::join!(
+        timed(check_database(pool)),
+        timed(check_migrations(pool)),
+        timed(check_uploads(&config.uploads.dir)),
+    );
+    BTreeMap::from([
+        ("database", database),
+        ("pool", pool_status),
+        ("migrations", schema),
+        ("uploads", uploads),
+    ])
+}
+
+fn all_ok(components: &BTreeMap<&'static str, ComponentHealth>) -> bool {
+    components.values().all(|c| c.status == CheckStatus::Ok)
+}
+
+async fn timed<F>(check: F) -> ComponentHealth
+where
+    F: Future<Output = Result<Value, String>>,
+{
+    let started = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, check).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let (status, error, details) = match result {
+        Ok(Ok(details)) => (CheckStatus::Ok, None, details),
+        Ok(Err(e)) => (CheckStatus::Fail, Some(e), Value::Null),
+        Err(_) => (CheckStatus::Timeout, Some(format!("sin respuesta en {}s", CHECK_TIMEOUT.as_secs())), Value::Null),
+    };
+    ComponentHealth { status, latency_ms, error, details }
+}
+
+// Ida y vuelta a Postgres
+async fn check_database(pool: &DbPool) -> Result<Value, String> {
+    let version = sqlx::query_scalar!(r#"SELECT current_setting('server_version') AS "version!""#)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(json!({ "server_version": version }))
+}
+
+// Todas las conexiones ocupadas: las peticiones nuevas esperarían a que se libere alguna
+fn check_pool(pool: &DbPool) -> Result<Value, String> {
+    let max = pool.options().get_max_connections();
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+    let in_use = size.saturating_sub(idle);
+    if in_use >= max {
+        return Err(format!("saturado: {} de {} conexiones en uso", in_use, max));
+    }
+    Ok(json!({ "size": size, "idle": idle, "in_use": in_use, "max": max }))
+}
+
+// Mismo criterio que al arrancar (db::migrations::check)
+async fn check_migrations(pool: &DbPool) -> Result<Value, String> {
+    let status = migrations::status(pool).await.map_err(|problems| problems.join("; "))?;
+    Ok(json!({
+        "latest_applied": status.latest_applied,
+        "expected": migrations::MIGRATOR.iter().last().map(|m| m.version),
+        "ahead": status.ahead,
+    }))
+}
+
+// Se puede escribir en la carpeta de subidas (volumen montado, permisos, disco lleno...)
+async fn check_uploads(dir: &Path) -> Result<Value, String> {
+    let probe = dir.join(format!(".readyz-{}", Uuid::new_v4()));
+    let result = async {
+        tokio::fs::create_dir_all(dir).await?;
+        tokio::fs::write(&probe, b"ok").await?;
+        tokio::fs::remove_file(&probe).await
+    }
+    .await;
+    result.map_err(|e| format!("{}: {}", dir.display(), e))?;
+    Ok(json!({ "dir": dir.display().to_string() }))
+}
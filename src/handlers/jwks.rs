@@ -0,0 +1,11 @@
+use axum::{extract::State, http::header, response::IntoResponse, Json};
+use std::sync::Arc;
+use crate::utils::jwt_keys::JwtKeys;
+
+// GET /.well-known/jwks.json - claves públicas para que otros servicios verifiquen nuestros JWT
+pub async fn jwks_handler(State(keys): State<Arc<JwtKeys>>) -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(keys.jwks.clone()),
+    )
+}
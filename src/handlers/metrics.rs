@@ -0,0 +1,37 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use std::sync::Arc;
+use crate::{
+    config::AppConfig,
+    db::DbPool,
+    error::ApiError,
+    utils::{metrics::Metrics, security::constant_time_eq},
+};
+
+// GET /metrics - formato de texto de Prometheus. Con [metrics] token exige
+// "Authorization: Bearer <token>" (el bearer_token del scrape_config)
+pub async fn metrics_handler(
+    State(metrics): State<Metrics>,
+    State(pool): State<DbPool>,
+    State(config): State<Arc<AppConfig>>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<Response, ApiError> {
+    if let Some(expected) = &config.metrics.token {
+        let valid = bearer.is_some_and(|TypedHeader(auth)| constant_time_eq(auth.token().as_bytes(), expected.as_bytes()));
+        if !valid {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED, "invalid_metrics_token", "Token de métricas inválido"));
+        }
+    }
+
+    let body = metrics
+        .render(&pool)
+        .ok_or_else(|| ApiError::not_found("Métricas desactivadas"))?;
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response())
+}
@@ -5,3 +5,12 @@ pub mod upload;
 pub mod site_config;
 pub mod tag;
 pub mod health;
+pub mod session;
+pub mod user;
+pub mod two_factor;
+pub mod api_key;
+pub mod jwks;
+pub mod oidc;
+pub mod author;
+pub mod audit;
+pub mod metrics;
@@ -0,0 +1,371 @@
+use axum::{
+    extract::{Json, State},
+    http::{header::SET_COOKIE, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_extra::TypedHeader;
+use chrono::{Duration, Utc};
+use headers::Cookie;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use crate::{
+    config::{AppConfig, Environment},
+    db::DbPool,
+    error::{ApiError, ApiQuery},
+    handlers::{auth::start_session, two_factor},
+    models::{role::Role, user::{AuthResponse, User}},
+    utils::{
+        audit::{self, AuditEntry},
+        jobs::Jobs,
+        jwt_keys::JwtKeys,
+        login_guard,
+        metrics,
+        oidc::{self, Identity, OidcClient},
+        request_info::ClientInfo,
+        security::constant_time_eq,
+        slug::slugify,
+    },
+};
+
+const STATE_MINUTES: i64 = 10;
+
+// El state viaja también en esta cookie (HttpOnly, solo para /api/auth/oidc): el callback exige
+// que coincidan, así un state/code obtenido por otro no sirve para iniciar sesión en este
+// navegador (login CSRF)
+const STATE_COOKIE: &str = "ctv_oidc_state";
+
+fn state_cookie(value: &str, max_age_secs: i64, environment: Environment) -> String {
+    let secure = if environment == Environment::Production { "; Secure" } else { "" };
+    format!(
+        "{}={}; Path=/api/auth/oidc; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        STATE_COOKIE, value, max_age_secs, secure
+    )
+}
+
+// Parámetros con los que el IdP vuelve al callback
+#[derive(Debug, Deserialize)]
+pub struct CallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+// GET /api/auth/oidc/login - redirige al IdP (authorization code + PKCE)
+pub async fn oidc_login_handler(
+    State(pool): State<DbPool>,
+    State(app_config): State<Arc<AppConfig>>,
+    State(client): State<OidcClient>,
+    State(jobs): State<Jobs>,
+) -> Result<impl IntoResponse, ApiError> {
+    let config = app_config.oidc.as_ref().ok_or_else(sso_disabled)?;
+    let metadata = idp_metadata(&client, config).await?;
+
+    let state = oidc::random_token();
+    let nonce = oidc::random_token();
+    let code_verifier = oidc::random_token();
+
+    // Aprovechamos para barrer estados abandonados, sin retrasar la redirección
+    let sweep_pool = pool.clone();
+    jobs.spawn("oidc_state_sweep", async move {
+        sqlx::query!("DELETE FROM oidc_login_states WHERE expires_at < NOW()")
+            .execute(&sweep_pool)
+            .await
+            .map(|_| ())
+    });
+
+    sqlx::query!(
+        "INSERT INTO oidc_login_states (state, code_verifier, nonce, expires_at) VALUES ($1, $2, $3, $4)",
+        state,
+        code_verifier,
+        nonce,
+        Utc::now() + Duration::minutes(STATE_MINUTES)
+    )
+    .execute(&pool)
+    .await
+    .map_err(ApiError::db("Error guardando estado OIDC"))?;
+
+    let url = reqwest::Url::parse_with_params(
+        &metadata.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", config.client_id.as_str()),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("scope", "openid email profile"),
+            ("state", state.as_str()),
+            ("nonce", nonce.as_str()),
+            ("code_challenge", oidc::pkce_challenge(&code_verifier).as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(|e| {
+        tracing::error!("authorization_endpoint inválido: {:?}", e);
+        ApiError::bad_gateway("Proveedor de identidad mal configurado")
+    })?;
+
+    let cookie = state_cookie(&state, STATE_MINUTES * 60, app_config.server.environment);
+    Ok(([(SET_COOKIE, cookie)], Redirect::to(url.as_str())))
+}
+
+fn sso_disabled() -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "sso_disabled", "SSO no configurado")
+}
+
+async fn idp_metadata<'a>(
+    client: &'a OidcClient,
+    config: &oidc::OidcConfig,
+) -> Result<&'a oidc::ProviderMetadata, ApiError> {
+    client.metadata(config).await.map_err(|e| {
+        tracing::error!("Error en discovery OIDC: {}", e);
+        ApiError::bad_gateway("Proveedor de identidad no disponible")
+    })
+}
+
+// GET /api/auth/oidc/callback - canjea el código, enlaza o crea el usuario y emite nuestro JWT
+pub async fn oidc_callback_handler(
+    State(pool): State<DbPool>,
+    State(app_config): State<Arc<AppConfig>>,
+    State(keys): State<Arc<JwtKeys>>,
+    State(client): State<OidcClient>,
+    device: ClientInfo,
+    cookies: Option<TypedHeader<Cookie>>,
+    ApiQuery(params): ApiQuery<CallbackParams>,
+) -> Result<Response, ApiError> {
+    let config = app_config.oidc.as_ref().ok_or_else(sso_disabled)?;
+
+    if let Some(error) = params.error {
+        tracing::warn!("oidc_login_failed reason=idp_error error={}", error);
+        metrics::record_login("oidc", "failure");
+        return Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "sso_rejected",
+            "El proveedor de identidad rechazó el login",
+        ));
+    }
+    let (Some(code), Some(state)) = (params.code, params.state) else {
+        return Err(ApiError::bad_request("Faltan code o state"));
+    };
+    let invalid_state = || ApiError::new(StatusCode::BAD_REQUEST, "invalid_state", "State inválido o caducado");
+
+    // Mismo navegador que empezó el login
+    let cookie_state = cookies.as_ref().and_then(|TypedHeader(cookies)| cookies.get(STATE_COOKIE));
+    if !cookie_state.is_some_and(|c| constant_time_eq(c.as_bytes(), state.as_bytes())) {
+        tracing::warn!("oidc_login_failed reason=state_cookie_mismatch");
+        metrics::record_login("oidc", "failure");
+        return Err(invalid_state());
+    }
+
+    // El state es de un solo uso: lo consumimos aunque el resto falle
+    let login_state = sqlx::query!(
+        "DELETE FROM oidc_login_states WHERE state = $1 AND expires_at > NOW() RETURNING code_verifier, nonce",
+        state
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(ApiError::db("Error consumiendo estado OIDC"))?
+    .ok_or_else(invalid_state)?;
+
+    let metadata = idp_metadata(&client, config).await?;
+
+    let identity = client.exchange_code(config, metadata, &code, &login_state.code_verifier, &login_state.nonce)
+        .await
+        .map_err(|e| {
+            tracing::warn!("oidc_login_failed reason=invalid_token detail={}", e);
+            metrics::record_login("oidc", "failure");
+            ApiError::new(StatusCode::UNAUTHORIZED, "invalid_identity", "No se pudo validar la identidad")
+        })?;
+
+    // Sin grupo mapeado no hay acceso a la redacción
+    let Some(role) = oidc::role_for(config, &identity.groups) else {
+        tracing::warn!("oidc_login_failed reason=no_role email={} groups={:?}", identity.email, identity.groups);
+        record_refusal(&pool, &identity, device.ip, json!({ "reason": "no_role", "groups": identity.groups })).await;
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "no_role_mapped",
+            "Tu cuenta no tiene acceso a la redacción",
+        ));
+    };
+
+    // Identidad ya enlazada (por sub): el rol lo manda siempre el IdP
+    let by_subject = sqlx::query!(
+        "UPDATE users SET role = $2 WHERE oidc_subject = $1 RETURNING id, email",
+        identity.subject,
+        role.as_str()
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(ApiError::db("Error enlazando usuario SSO"))?;
+
+    let linked = match by_subject {
+        Some(u) => Some((u.id, u.email)),
+        None => link_by_email(&pool, &identity, role, &device.ip).await?,
+    };
+
+    let user = match linked {
+        Some(user) => user,
+        None => {
+            // Alta automática: sin contraseña utilizable ("!" no es un hash válido),
+            // username = preferred_username si está libre. Nunca el email: el username sale en
+            // los bylines públicos
+            let fallback_username = public_username(&identity.email);
+            let created = sqlx::query!(
+                r#"
+                INSERT INTO users (username, email, password_hash, role, oidc_subject, author_slug)
+                VALUES (
+                    CASE WHEN $1::text IS NULL OR EXISTS (SELECT 1 FROM users WHERE username = $1)
+                         THEN $6 ELSE $1 END,
+                    $2, '!', $3, $4,
+                    (SELECT $5::text WHERE $5 <> '' AND NOT EXISTS (SELECT 1 FROM users WHERE author_slug = $5))
+                )
+                RETURNING id, email
+                "#,
+                identity.username,
+                identity.email,
+                role.as_str(),
+                identity.subject,
+                slugify(identity.username.as_deref().unwrap_or(&fallback_username)),
+                fallback_username
+            )
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::Database(db) if db.is_unique_violation() => {
+                    tracing::warn!("oidc_login_failed reason=email_linked email={}", identity.email);
+                    metrics::record_login("oidc", "failure");
+                    ApiError::conflict("El email ya está vinculado a otra identidad")
+                }
+                e => ApiError::db("Error creando usuario SSO")(e),
+            })?;
+
+            tracing::info!("oidc_user_provisioned user_id={} role={}", created.id, role);
+            (created.id, created.email)
+        }
+    };
+    let (user_id, email) = user;
+    let clear_cookie = [(SET_COOKIE, state_cookie("", 0, app_config.server.environment))];
+
+    // Segundo factor: igual que en el login con contraseña, con TOTP activo (u obligatorio y aún
+    // sin activar) la sesión se emite en POST /api/auth/2fa/verify
+    let account = sqlx::query_as!(
+        User,
+        "SELECT id, username, email, password_hash, role, totp_enabled, totp_required FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(ApiError::db("Error leyendo usuario SSO"))?;
+    if account.totp_enabled || account.totp_required {
+        tracing::info!("oidc_login_challenge user_id={}", user_id);
+        metrics::record_login("oidc", "challenge");
+        let challenge = two_factor::challenge(&pool, &app_config.auth, &keys, &account).await?;
+        return Ok(match &config.post_login_redirect {
+            Some(url) => {
+                let fragment = serde_urlencoded::to_string(&challenge).map_err(|e| {
+                    tracing::error!("Error serializando el reto 2FA: {}", e);
+                    ApiError::internal()
+                })?;
+                (clear_cookie, Redirect::to(&format!("{}#{}", url, fragment))).into_response()
+            }
+            None => (clear_cookie, Json(challenge)).into_response(),
+        });
+    }
+
+    if let Err(e) = login_guard::clear_account(&pool, &email).await {
+        tracing::error!("Error limpiando intentos fallidos: {:?}", e);
+    }
+
+    let token = start_session(&pool, &app_config.auth, &keys, user_id, &email, role, &device).await?;
+    tracing::info!("oidc_login user_id={} role={}", user_id, role);
+    metrics::record_login("oidc", "success");
+    audit::record(
+        &pool,
+        AuditEntry::new("auth.login", "user", user_id)
+            .login_actor(Some(user_id), &email)
+            .ip(device.ip)
+            .after(&serde_json::json!({ "method": "oidc", "role": role })),
+    )
+    .await;
+
+    // Con panel configurado devolvemos el token en el fragmento (no llega a logs de servidores)
+    Ok(match &config.post_login_redirect {
+        Some(url) => (clear_cookie, Redirect::to(&format!("{}#token={}&token_type=Bearer", url, token))).into_response(),
+        None => (clear_cookie, Json(AuthResponse {
+            token,
+            token_type: "Bearer".to_string(),
+            recovery_codes: None,
+        })).into_response(),
+    })
+}
+
+// Parte local del email + sufijo aleatorio ("juan.perez@ctv.com" -> "juan.perez-3f9a1c")
+fn public_username(email: &str) -> String {
+    let local: String = email.split('@').next().unwrap_or_default().chars().take(80).collect();
+    let suffix: String = oidc::random_token().chars().filter(char::is_ascii_alphanumeric).take(6).collect();
+    format!("{}-{}", if local.is_empty() { "usuario" } else { &local }, suffix.to_lowercase())
+}
+
+// Primer login SSO de una cuenta local: se enlaza por email solo si el IdP lo da por verificado
+// (si no, quien registre ese email en el IdP se quedaría con la cuenta) y nunca rebajando a un
+// admin en silencio. None si no hay cuenta local con ese email (se da de alta una nueva)
+async fn link_by_email(
+    pool: &DbPool,
+    identity: &Identity,
+    role: Role,
+    ip: &str,
+) -> Result<Option<(i64, String)>, ApiError> {
+    let existing = sqlx::query!(
+        "SELECT id, role FROM users WHERE email = $1 AND oidc_subject IS NULL",
+        identity.email
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(ApiError::db("Error buscando usuario SSO"))?;
+    let Some(existing) = existing else { return Ok(None) };
+
+    if !identity.email_verified {
+        tracing::warn!("oidc_login_failed reason=email_not_verified user_id={}", existing.id);
+        record_refusal(pool, identity, ip.to_string(), json!({ "reason": "email_not_verified" })).await;
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "email_not_verified",
+            "El proveedor de identidad no confirma tu email: no se puede vincular a la cuenta existente",
+        ));
+    }
+    if existing.role == Role::Admin.as_str() && role != Role::Admin {
+        tracing::warn!("oidc_login_failed reason=admin_downgrade user_id={} role={}", existing.id, role);
+        record_refusal(pool, identity, ip.to_string(), json!({ "reason": "admin_downgrade", "role": role })).await;
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "sso_link_refused",
+            "La cuenta es de administrador y el SSO le daría otro rol; un admin debe revisar el mapeo de grupos",
+        ));
+    }
+
+    // oidc_subject IS NULL otra vez: otra petición pudo enlazarla entre medias
+    let linked = sqlx::query!(
+        "UPDATE users SET role = $3, oidc_subject = $2 WHERE id = $1 AND oidc_subject IS NULL RETURNING id, email",
+        existing.id,
+        identity.subject,
+        role.as_str()
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(ApiError::db("Error enlazando usuario SSO"))?;
+    if let Some(user) = &linked {
+        tracing::info!("oidc_account_linked user_id={} role={}", user.id, role);
+    }
+    Ok(linked.map(|u| (u.id, u.email)))
+}
+
+async fn record_refusal(pool: &DbPool, identity: &Identity, ip: String, mut details: Value) {
+    metrics::record_login("oidc", "failure");
+    details["method"] = json!("oidc");
+    audit::record(
+        pool,
+        AuditEntry::new("auth.login_failed", "user", &identity.email)
+            .login_actor(None, &identity.email)
+            .ip(ip)
+            .after(&details),
+    )
+    .await;
+}
@@ -0,0 +1,91 @@
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+use uuid::Uuid;
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiPath},
+    models::{session::Session, user::Claims},
+    utils::{audit::{self, AuditEntry}, request_info::ClientIp},
+};
+
+// GET /api/me/sessions - sesiones activas del usuario del token
+pub async fn list_my_sessions_handler(
+    State(pool): State<DbPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ApiError> {
+    let sessions = sqlx::query_as!(
+        Session,
+        r#"
+        SELECT id, user_agent, ip_address, created_at, last_used_at, expires_at,
+               (id = $2) as "is_current!: bool"
+        FROM user_sessions
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY last_used_at DESC
+        "#,
+        claims.user_id,
+        claims.sid
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(ApiError::db("Error listando sesiones"))?;
+
+    Ok((StatusCode::OK, Json(sessions)))
+}
+
+// DELETE /api/me/sessions/:id - cerrar una sesión propia (incluida la actual = logout)
+pub async fn revoke_my_session_handler(
+    ApiPath(session_id): ApiPath<Uuid>,
+    State(pool): State<DbPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ApiError> {
+    let result = sqlx::query!(
+        "UPDATE user_sessions SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL",
+        session_id,
+        claims.user_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(ApiError::db("Error revocando sesión"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::not_found("Sesión no encontrada"));
+    }
+    tracing::info!("session_revoked id={} user_id={}", session_id, claims.user_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// DELETE /api/admin/users/:id/sessions (admin) - cuenta comprometida: cerrar todas sus sesiones
+pub async fn revoke_user_sessions_handler(
+    ApiPath(user_id): ApiPath<i64>,
+    State(pool): State<DbPool>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ApiError> {
+    let result = sqlx::query!(
+        "UPDATE user_sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+        user_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(ApiError::db("Error revocando sesiones de usuario"))?;
+
+    tracing::info!(
+        "sessions_revoked user_id={} count={} by_admin={}",
+        user_id,
+        result.rows_affected(),
+        claims.user_id
+    );
+    audit::record(
+        &pool,
+        AuditEntry::new("user.sessions_revoke", "user", user_id)
+            .actor(&claims)
+            .ip(&ip)
+            .after(&serde_json::json!({ "revoked": result.rows_affected() })),
+    )
+    .await;
+    Ok((StatusCode::OK, Json(serde_json::json!({ "revoked": result.rows_affected() }))))
+}
@@ -1,41 +1,65 @@
-use axum::{extract::{State, Json}, http::StatusCode, response::IntoResponse};
-use crate::{db::DbPool, models::site_config::{SiteConfig, UpdateSiteConfigSchema}};
+use axum::{
+    extract::{State, Json},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+use crate::{
+    db::DbPool,
+    error::{ApiError, ValidJson},
+    models::{site_config::{SiteConfig, UpdateSiteConfigSchema}, user::Claims},
+    utils::{
+        audit::{self, AuditEntry},
+        cache::{CacheKey, ResponseCache},
+        request_info::ClientIp,
+    },
+};
 
 // GET /api/site-config (público)
-pub async fn get_site_config_handler(State(pool): State<DbPool>) -> impl IntoResponse {
-    let result = sqlx::query_as!(
-        SiteConfig,
-        r#"SELECT id, live_stream_url, is_live_active as "is_live_active!: bool", breaking_news_banner FROM site_config WHERE id = 1"#
-    )
-    .fetch_optional(&pool)
-    .await;
-
-    match result {
-        Ok(Some(cfg)) => (StatusCode::OK, Json(cfg)).into_response(),
-        Ok(None) => {
+pub async fn get_site_config_handler(
+    State(pool): State<DbPool>,
+    State(cache): State<ResponseCache>,
+) -> Result<impl IntoResponse, ApiError> {
+    cache
+        .get_or_load(CacheKey::SiteConfig, || async move {
+            let cfg = sqlx::query_as!(
+                SiteConfig,
+                r#"SELECT id, live_stream_url, is_live_active as "is_live_active!: bool", breaking_news_banner FROM site_config WHERE id = 1"#
+            )
+            .fetch_optional(&pool)
+            .await
+            .map_err(ApiError::db("Error leyendo site_config"))?
             // Si no existe, devolvemos defaults
-            let cfg = SiteConfig {
+            .unwrap_or(SiteConfig {
                 id: 1,
                 live_stream_url: None,
                 is_live_active: true,
                 breaking_news_banner: None,
-            };
-            (StatusCode::OK, Json(cfg)).into_response()
-        }
-        Err(e) => {
-            tracing::error!("Error leyendo site_config: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+            });
+            Ok(cfg)
+        })
+        .await
 }
 
 // PUT /api/admin/site-config (admin)
 pub async fn update_site_config_handler(
     State(pool): State<DbPool>,
-    Json(body): Json<UpdateSiteConfigSchema>,
-) -> impl IntoResponse {
+    State(cache): State<ResponseCache>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+    ValidJson(body): ValidJson<UpdateSiteConfigSchema>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Estado previo para la auditoría (None si aún no existía la fila)
+    let before = sqlx::query_as!(
+        SiteConfig,
+        r#"SELECT id, live_stream_url, is_live_active as "is_live_active!: bool", breaking_news_banner FROM site_config WHERE id = 1"#
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(ApiError::db("Error leyendo site_config"))?;
+
     // Upsert sencillo
-    let result = sqlx::query_as!(
+    let cfg = sqlx::query_as!(
         SiteConfig,
         r#"
         INSERT INTO site_config (id, live_stream_url, is_live_active, breaking_news_banner)
@@ -51,13 +75,18 @@ pub async fn update_site_config_handler(
         body.breaking_news_banner
     )
     .fetch_one(&pool)
-    .await;
+    .await
+    .map_err(ApiError::db("Error actualizando site_config"))?;
+    cache.invalidate(&[CacheKey::SiteConfig]);
 
-    match result {
-        Ok(cfg) => (StatusCode::OK, Json(cfg)).into_response(),
-        Err(e) => {
-            tracing::error!("Error actualizando site_config: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    audit::record(
+        &pool,
+        AuditEntry::new("site_config.update", "site_config", cfg.id)
+            .actor(&claims)
+            .ip(&ip)
+            .before(&before)
+            .after(&cfg),
+    )
+    .await;
+    Ok((StatusCode::OK, Json(cfg)))
 }
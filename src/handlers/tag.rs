@@ -1,5 +1,19 @@
-use axum::{extract::{Json, State}, http::StatusCode, response::IntoResponse};
-use crate::{db::DbPool, models::tag::{Tag, CreateTagSchema}};
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiJson, ApiPath, ValidJson},
+    models::{tag::{Tag, CreateTagSchema}, user::Claims},
+    utils::{
+        audit::{self, AuditEntry},
+        cache::{CacheKey, ResponseCache},
+        request_info::ClientIp,
+    },
+};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -8,29 +22,24 @@ pub struct TagAssignment {
 }
 
 // GET /api/tags (público)
-pub async fn list_tags_handler(State(pool): State<DbPool>) -> impl IntoResponse {
-    let result = sqlx::query_as!(
+pub async fn list_tags_handler(State(pool): State<DbPool>) -> Result<impl IntoResponse, ApiError> {
+    let tags = sqlx::query_as!(
         Tag,
         r#"SELECT id, name, slug FROM tags ORDER BY name ASC"#
     )
     .fetch_all(&pool)
-    .await;
+    .await
+    .map_err(ApiError::db("Error listando tags"))?;
 
-    match result {
-        Ok(tags) => (StatusCode::OK, axum::Json(tags)).into_response(),
-        Err(e) => {
-            tracing::error!("Error listando tags: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    Ok((StatusCode::OK, Json(tags)))
 }
 
 // GET /api/articles/:slug/tags
 pub async fn list_article_tags_handler(
-    axum::extract::Path(slug): axum::extract::Path<String>,
+    ApiPath(slug): ApiPath<String>,
     State(pool): State<DbPool>,
-) -> impl IntoResponse {
-    let result = sqlx::query_as!(
+) -> Result<impl IntoResponse, ApiError> {
+    let tags = sqlx::query_as!(
         Tag,
         r#"
         SELECT t.id, t.name, t.slug
@@ -43,89 +52,89 @@ pub async fn list_article_tags_handler(
         slug
     )
     .fetch_all(&pool)
-    .await;
+    .await
+    .map_err(ApiError::db("Error listando tags de artículo"))?;
 
-    match result {
-        Ok(tags) => (StatusCode::OK, axum::Json(tags)).into_response(),
-        Err(e) => {
-            tracing::error!("Error listando tags de artículo: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Error interno").into_response()
-        }
-    }
+    Ok((StatusCode::OK, Json(tags)))
 }
 
 // POST /api/admin/articles/:id/tags (admin) - reemplaza set completo
+// (tag o noticia inexistente -> 422 por la FK; el rollback lo hace el drop de la transacción)
 pub async fn set_article_tags_handler(
-    axum::extract::Path(article_id): axum::extract::Path<i64>,
+    ApiPath(article_id): ApiPath<i64>,
     State(pool): State<DbPool>,
-    Json(body): Json<TagAssignment>,
-) -> impl IntoResponse {
-    let mut tx = match pool.begin().await {
-        Ok(tx) => tx,
-        Err(e) => {
-            tracing::error!("Error iniciando transacción: {:?}", e);
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-        }
-    };
-
-    // Limpiar tags existentes
-    if let Err(e) = sqlx::query!("DELETE FROM article_tags WHERE article_id = $1", article_id)
-        .execute(&mut *tx)
-        .await
-    {
-        tracing::error!("Error limpiando tags: {:?}", e);
-        let _ = tx.rollback().await;
-        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-    }
+    State(cache): State<ResponseCache>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+    ApiJson(body): ApiJson<TagAssignment>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut tx = pool.begin().await.map_err(ApiError::db("Error iniciando transacción"))?;
+
+    // Limpiar tags existentes (guardando cuáles eran para la auditoría)
+    let previous = sqlx::query_scalar!(
+        "DELETE FROM article_tags WHERE article_id = $1 RETURNING tag_id",
+        article_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(ApiError::db("Error limpiando tags"))?;
 
     // Insertar nuevos
     for tag_id in body.tag_ids.iter() {
-        if let Err(e) = sqlx::query!(
+        sqlx::query!(
             "INSERT INTO article_tags (article_id, tag_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
             article_id,
             tag_id
         )
         .execute(&mut *tx)
         .await
-        {
-            tracing::error!("Error insertando tag {}: {:?}", tag_id, e);
-            let _ = tx.rollback().await;
-            return StatusCode::BAD_REQUEST.into_response();
-        }
+        .map_err(ApiError::db("Error insertando tag"))?;
     }
 
-    if let Err(e) = tx.commit().await {
-        tracing::error!("Error commit tags: {:?}", e);
-        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-    }
+    tx.commit().await.map_err(ApiError::db("Error commit tags"))?;
+    cache.invalidate(&CacheKey::ARTICLE_LISTS);
 
     tracing::info!("tags_set article_id={} tags={:?}", article_id, body.tag_ids);
-    StatusCode::NO_CONTENT.into_response()
+    audit::record(
+        &pool,
+        AuditEntry::new("article.tags_set", "article", article_id)
+            .actor(&claims)
+            .ip(&ip)
+            .before(&serde_json::json!({ "tag_ids": previous }))
+            .after(&serde_json::json!({ "tag_ids": body.tag_ids })),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
 }
 
-// POST /api/admin/tags (admin)
+// POST /api/admin/tags (admin) - slug repetido -> 409
 pub async fn create_tag_handler(
     State(pool): State<DbPool>,
-    Json(body): Json<CreateTagSchema>,
-) -> impl IntoResponse {
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+    ValidJson(body): ValidJson<CreateTagSchema>,
+) -> Result<impl IntoResponse, ApiError> {
     let slug = body.slug.clone().unwrap_or_else(|| slugify(&body.name));
 
-    let result = sqlx::query_as!(
+    let tag = sqlx::query_as!(
         Tag,
         r#"INSERT INTO tags (name, slug) VALUES ($1, $2) RETURNING id, name, slug"#,
         body.name,
         slug
     )
     .fetch_one(&pool)
+    .await
+    .map_err(ApiError::db("Error creando tag"))?;
+
+    audit::record(
+        &pool,
+        AuditEntry::new("tag.create", "tag", tag.id)
+            .actor(&claims)
+            .ip(&ip)
+            .after(&tag),
+    )
     .await;
-
-    match result {
-        Ok(tag) => (StatusCode::CREATED, axum::Json(tag)).into_response(),
-        Err(e) => {
-            tracing::error!("Error creando tag: {:?}", e);
-            (StatusCode::BAD_REQUEST, "No se pudo crear el tag").into_response()
-        }
-    }
+    Ok((StatusCode::CREATED, Json(tag)))
 }
 
 fn slugify(input: &str) -> String {
@@ -0,0 +1,444 @@
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use crate::{
+    config::{AppConfig, AuthConfig},
+    db::DbPool,
+    error::{ApiError, ApiJson, ApiPath},
+    handlers::auth::start_session,
+    models::{
+        role::Role,
+        two_factor::{
+            ChallengeClaims, ChallengeResponse, RecoveryCodesResponse, TwoFactorCodePayload,
+            TwoFactorRequiredPayload, TwoFactorSetupResponse, VerifyChallengePayload,
+        },
+        user::{AuthResponse, Claims, User},
+    },
+    utils::{
+        audit::{self, AuditEntry},
+        jwt::{decode_challenge, encode_challenge},
+        jwt_keys::JwtKeys,
+        login_guard,
+        metrics,
+        request_info::{ClientInfo, ClientIp},
+        security::{hash_password, verify_password},
+        totp,
+    },
+};
+
+const PURPOSE_VERIFY: &str = "2fa_verify";
+const PURPOSE_ENROLL: &str = "2fa_enroll";
+
+// Respuesta del login cuando falta el segundo factor (llamada desde auth::login_handler)
+pub async fn challenge_response(pool: &DbPool, config: &AuthConfig, keys: &JwtKeys, user: &User) -> Result<Response, ApiError> {
+    Ok((StatusCode::OK, Json(challenge(pool, config, keys, user).await?)).into_response())
+}
+
+// Token del segundo factor (y, si toca enrolarse, el secreto nuevo). También lo usa el SSO:
+// entrar por el IdP no se salta el TOTP de la cuenta
+pub async fn challenge(pool: &DbPool, config: &AuthConfig, keys: &JwtKeys, user: &User) -> Result<ChallengeResponse, ApiError> {
+    let mut enrollment = None;
+
+    // 2FA obligatorio pero sin activar: el usuario se enrola antes de obtener su JWT
+    if !user.totp_enabled {
+        let secret = totp::generate_secret();
+        sqlx::query!("UPDATE users SET totp_secret = $2 WHERE id = $1", user.id, secret)
+            .execute(pool)
+            .await
+            .map_err(ApiError::db("Error guardando secreto TOTP"))?;
+        let uri = totp::otpauth_uri(&secret, &user.email);
+        enrollment = Some((secret, uri));
+    }
+
+    let purpose = if enrollment.is_some() { PURPOSE_ENROLL } else { PURPOSE_VERIFY };
+    let claims = ChallengeClaims {
+        sub: user.email.clone(),
+        exp: (Utc::now() + Duration::minutes(config.two_factor_challenge_minutes)).timestamp() as usize,
+        iat: Utc::now().timestamp() as usize,
+        user_id: user.id,
+        purpose: purpose.to_string(),
+    };
+
+    let challenge_token = encode_challenge(keys, &claims)?;
+
+    let (secret, otpauth_uri) = match enrollment {
+        Some((secret, uri)) => (Some(secret), uri),
+        None => (None, None),
+    };
+
+    Ok(ChallengeResponse {
+        two_factor_required: true,
+        challenge_token,
+        expires_in: config.two_factor_challenge_minutes * 60,
+        otpauth_uri,
+        secret,
+    })
+}
+
+fn invalid_code() -> ApiError {
+    ApiError::new(StatusCode::UNAUTHORIZED, "invalid_code", "Código inválido")
+}
+
+// Verifica un código TOTP (o, si se permite, un código de recuperación y lo consume)
+async fn check_code(
+    pool: &DbPool,
+    user_id: i64,
+    code: &str,
+    allow_recovery: bool,
+) -> Result<bool, sqlx::Error> {
+    let row = sqlx::query!(
+        "SELECT totp_secret, totp_last_step FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let Some(secret) = row.totp_secret else { return Ok(false) };
+
+    // El UPDATE condicional es el que decide: de dos peticiones simultáneas con el mismo código
+    // solo una avanza el paso (anti-replay)
+    if let Some(step) = totp::verify(&secret, code, row.totp_last_step) {
+        let result = sqlx::query!(
+            "UPDATE users SET totp_last_step = $2 WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)",
+            user_id,
+            step
+        )
+        .execute(pool)
+        .await?;
+        return Ok(result.rows_affected() == 1);
+    }
+
+    if !allow_recovery {
+        return Ok(false);
+    }
+
+    let codes = sqlx::query!(
+        "SELECT id, code_hash FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let code = code.trim().to_lowercase();
+    for rc in codes {
+        if verify_password(&code, &rc.code_hash) {
+            // Igual que arriba: un código de recuperación solo lo gasta una petición
+            let result = sqlx::query!(
+                "UPDATE user_recovery_codes SET used_at = NOW() WHERE id = $1 AND used_at IS NULL",
+                rc.id
+            )
+            .execute(pool)
+            .await?;
+            if result.rows_affected() != 1 {
+                return Ok(false);
+            }
+            tracing::info!("recovery_code_used user_id={}", user_id);
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+// Activa el 2FA con el secreto pendiente y genera códigos de recuperación nuevos
+async fn activate(pool: &DbPool, user_id: i64) -> Result<Vec<String>, ApiError> {
+    let codes = totp::generate_recovery_codes();
+    let mut hashes = Vec::with_capacity(codes.len());
+    for code in codes.iter() {
+        hashes.push(hash_password(code).map_err(|_| ApiError::internal())?);
+    }
+
+    async {
+        let mut tx = pool.begin().await?;
+        sqlx::query!("UPDATE users SET totp_enabled = TRUE WHERE id = $1", user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM user_recovery_codes WHERE user_id = $1", user_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO user_recovery_codes (user_id, code_hash) SELECT $1, * FROM UNNEST($2::text[])",
+            user_id,
+            &hashes
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+    .await
+    .map_err(ApiError::db("Error activando 2FA"))?;
+
+    tracing::info!("2fa_enabled user_id={}", user_id);
+    Ok(codes)
+}
+
+// Quita secreto y códigos (desactivar o reset por admin)
+async fn clear(pool: &DbPool, user_id: i64) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let res = sqlx::query!(
+        "UPDATE users SET totp_secret = NULL, totp_enabled = FALSE, totp_last_step = NULL WHERE id = $1",
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!("DELETE FROM user_recovery_codes WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(res.rows_affected())
+}
+
+// POST /api/auth/2fa/verify - segundo paso del login
+pub async fn verify_challenge_handler(
+    State(pool): State<DbPool>,
+    State(config): State<Arc<AppConfig>>,
+    State(keys): State<Arc<JwtKeys>>,
+    device: ClientInfo,
+    ApiJson(payload): ApiJson<VerifyChallengePayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let challenge = match decode_challenge(&keys, &payload.challenge_token) {
+        Ok(c) if c.purpose == PURPOSE_VERIFY || c.purpose == PURPOSE_ENROLL => c,
+        _ => {
+            return Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "invalid_challenge",
+                "Desafío inválido o expirado",
+            ))
+        }
+    };
+
+    // Mismo freno anti fuerza bruta que el login (6 dígitos se adivinan rápido)
+    if login_guard::is_locked(&pool, &challenge.sub, &device.ip)
+        .await
+        .map_err(ApiError::db("Error consultando bloqueo de login"))?
+    {
+        metrics::record_login("2fa", "locked");
+        return Err(invalid_code());
+    }
+
+    let enrolling = challenge.purpose == PURPOSE_ENROLL;
+    let valid = check_code(&pool, challenge.user_id, &payload.code, !enrolling)
+        .await
+        .map_err(ApiError::db("Error verificando código 2FA"))?;
+
+    if !valid {
+        metrics::record_login("2fa", "failure");
+        audit::record(
+            &pool,
+            AuditEntry::new("auth.login_failed", "user", challenge.user_id)
+                .login_actor(Some(challenge.user_id), &challenge.sub)
+                .ip(device.ip.clone())
+                .after(&serde_json::json!({ "step": "2fa" })),
+        )
+        .await;
+        match login_guard::register_failure(&pool, &challenge.sub, &device.ip).await {
+            Ok(true) => {
+                audit::record(
+                    &pool,
+                    AuditEntry::new("auth.lockout", "user", challenge.user_id)
+                        .login_actor(Some(challenge.user_id), &challenge.sub)
+                        .ip(device.ip.clone()),
+                )
+                .await
+            }
+            Ok(false) => {}
+            Err(e) => tracing::error!("Error registrando intento fallido: {:?}", e),
+        }
+        return Err(invalid_code());
+    }
+
+    if let Err(e) = login_guard::clear_account(&pool, &challenge.sub).await {
+        tracing::error!("Error limpiando intentos fallidos: {:?}", e);
+    }
+
+    let recovery_codes = if enrolling {
+        Some(activate(&pool, challenge.user_id).await?)
+    } else {
+        None
+    };
+
+    // El rol se lee de nuevo: pudo cambiar entre el login y este paso
+    let role = sqlx::query_scalar!("SELECT role FROM users WHERE id = $1", challenge.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(ApiError::db("Error leyendo usuario"))?
+        .parse::<Role>()
+        .map_err(|e| {
+            tracing::error!("Usuario {} con rol inválido: {}", challenge.user_id, e);
+            ApiError::internal()
+        })?;
+
+    let token = start_session(&pool, &config.auth, &keys, challenge.user_id, &challenge.sub, role, &device).await?;
+    metrics::record_login("2fa", "success");
+    audit::record(
+        &pool,
+        AuditEntry::new("auth.login", "user", challenge.user_id)
+            .login_actor(Some(challenge.user_id), &challenge.sub)
+            .ip(device.ip)
+            .after(&serde_json::json!({ "step": "2fa" })),
+    )
+    .await;
+    Ok((StatusCode::OK, Json(AuthResponse {
+        token,
+        token_type: "Bearer".to_string(),
+        recovery_codes,
+    })))
+}
+
+// POST /api/me/2fa/setup - genera un secreto pendiente y devuelve la URI para el QR
+pub async fn setup_handler(
+    State(pool): State<DbPool>,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ApiError> {
+    let secret = totp::generate_secret();
+    let result = sqlx::query!(
+        "UPDATE users SET totp_secret = $2 WHERE id = $1 AND totp_enabled = FALSE",
+        claims.user_id,
+        secret
+    )
+    .execute(&pool)
+    .await
+    .map_err(ApiError::db("Error guardando secreto TOTP"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(already_enabled());
+    }
+    let otpauth_uri = totp::otpauth_uri(&secret, &claims.sub).ok_or_else(|| {
+        tracing::error!("Error generando URI otpauth para {}", claims.user_id);
+        ApiError::internal()
+    })?;
+    Ok((StatusCode::OK, Json(TwoFactorSetupResponse { secret, otpauth_uri })))
+}
+
+fn already_enabled() -> ApiError {
+    ApiError::new(StatusCode::CONFLICT, "two_factor_already_enabled", "El 2FA ya está activo")
+}
+
+// Código incorrecto en /api/me/2fa/*: el usuario ya está autenticado, es un 400 y no un 401
+fn wrong_code() -> ApiError {
+    ApiError::new(StatusCode::BAD_REQUEST, "invalid_code", "Código inválido")
+}
+
+// POST /api/me/2fa/enable - confirma el primer código y activa el 2FA
+pub async fn enable_handler(
+    State(pool): State<DbPool>,
+    Extension(claims): Extension<Claims>,
+    ApiJson(payload): ApiJson<TwoFactorCodePayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let enabled = sqlx::query_scalar!("SELECT totp_enabled FROM users WHERE id = $1", claims.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(ApiError::db("Error leyendo usuario"))?;
+    if enabled {
+        return Err(already_enabled());
+    }
+
+    if !check_code(&pool, claims.user_id, &payload.code, false)
+        .await
+        .map_err(ApiError::db("Error verificando código 2FA"))?
+    {
+        return Err(wrong_code());
+    }
+
+    let recovery_codes = activate(&pool, claims.user_id).await?;
+    Ok((StatusCode::OK, Json(RecoveryCodesResponse { recovery_codes })))
+}
+
+// POST /api/me/2fa/disable - requiere un código válido; no permitido si un admin lo exige
+pub async fn disable_handler(
+    State(pool): State<DbPool>,
+    Extension(claims): Extension<Claims>,
+    ApiJson(payload): ApiJson<TwoFactorCodePayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    let required = sqlx::query_scalar!("SELECT totp_required FROM users WHERE id = $1", claims.user_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(ApiError::db("Error leyendo usuario"))?;
+    if required {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "two_factor_required",
+            "El 2FA es obligatorio para tu cuenta",
+        ));
+    }
+
+    if !check_code(&pool, claims.user_id, &payload.code, true)
+        .await
+        .map_err(ApiError::db("Error verificando código 2FA"))?
+    {
+        return Err(wrong_code());
+    }
+
+    clear(&pool, claims.user_id).await.map_err(ApiError::db("Error desactivando 2FA"))?;
+    tracing::info!("2fa_disabled user_id={}", claims.user_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// DELETE /api/admin/users/:id/2fa (admin) - reset (móvil perdido); si es obligatorio, se re-enrola al entrar
+pub async fn reset_user_2fa_handler(
+    ApiPath(user_id): ApiPath<i64>,
+    State(pool): State<DbPool>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ApiError> {
+    if clear(&pool, user_id).await.map_err(ApiError::db("Error reseteando 2FA"))? == 0 {
+        return Err(ApiError::not_found("Usuario no encontrado"));
+    }
+
+    tracing::info!("2fa_reset user_id={} by_admin={}", user_id, claims.user_id);
+    audit::record(
+        &pool,
+        AuditEntry::new("user.2fa_reset", "user", user_id)
+            .actor(&claims)
+            .ip(&ip),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// PUT /api/admin/users/:id/2fa (admin) - exigir (o no) 2FA a un usuario
+pub async fn set_user_2fa_required_handler(
+    ApiPath(user_id): ApiPath<i64>,
+    State(pool): State<DbPool>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+    ApiJson(payload): ApiJson<TwoFactorRequiredPayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    // El valor anterior sale del propio UPDATE (subconsulta sobre la fila antes del cambio)
+    let previous = sqlx::query_scalar!(
+        r#"
+        UPDATE users u SET totp_required = $2
+        FROM users old
+        WHERE u.id = $1 AND old.id = u.id
+        RETURNING old.totp_required
+        "#,
+        user_id,
+        payload.required
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(ApiError::db("Error actualizando 2FA obligatorio"))?
+    .ok_or_else(|| ApiError::not_found("Usuario no encontrado"))?;
+
+    tracing::info!(
+        "2fa_required user_id={} required={} by_admin={}",
+        user_id,
+        payload.required,
+        claims.user_id
+    );
+    audit::record(
+        &pool,
+        AuditEntry::new("user.2fa_required", "user", user_id)
+            .actor(&claims)
+            .ip(&ip)
+            .before(&serde_json::json!({ "totp_required": previous }))
+            .after(&serde_json::json!({ "totp_required": payload.required })),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
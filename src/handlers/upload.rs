@@ -1,22 +1,27 @@
 use axum::{
-    extract::Multipart,
+    extract::{multipart::MultipartRejection, Multipart, State},
     http::StatusCode,
     response::{IntoResponse, Json},
 };
 use serde_json::json;
-use std::path::Path;
+use crate::{config::AppConfig, error::ApiError, utils::metrics};
+use std::{path::Path, sync::Arc};
 use tokio::fs; // Usamos el sistema de archivos asíncrono
 use uuid::Uuid;
 use mime::Mime;
 
-// Configuración: Carpeta donde se guardarán las fotos
-const UPLOAD_DIR: &str = "uploads";
-const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024; // 5MB
+// Carpeta y tamaño máximo salen de la config ([uploads] dir / max_image_bytes)
+pub async fn upload_image_handler(
+    State(config): State<Arc<AppConfig>>,
+    multipart: Result<Multipart, MultipartRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let mut multipart = multipart?;
+    let upload_dir = &config.uploads.dir;
+    let max_bytes = config.uploads.max_image_bytes;
 
-pub async fn upload_image_handler(mut multipart: Multipart) -> impl IntoResponse {
-    // 1. Crear la carpeta 'uploads' si no existe
-    if !Path::new(UPLOAD_DIR).exists() {
-        let _ = fs::create_dir_all(UPLOAD_DIR).await;
+    // 1. Crear la carpeta de subidas si no existe
+    if !upload_dir.exists() {
+        let _ = fs::create_dir_all(upload_dir).await;
     }
 
     // 2. Buscar el campo "image" en el formulario enviado
@@ -37,17 +42,19 @@ pub async fn upload_image_handler(mut multipart: Multipart) -> impl IntoResponse
 
             // 3. Generar nombre único (Ej: 550e8400-e29b....jpg)
             let new_filename = format!("{}.{}", Uuid::new_v4(), extension);
-            let filepath = format!("{}/{}", UPLOAD_DIR, new_filename);
+            let filepath = upload_dir.join(&new_filename);
 
             // 4. Leer los bytes del archivo
+            //    (si el cuerpo supera el límite de la ruta, axum corta la lectura con un 413)
             let data = match field.bytes().await {
                 Ok(bytes) => bytes,
-                Err(_) => return (StatusCode::BAD_REQUEST, "Error al leer el archivo").into_response(),
+                Err(e) if e.status() == StatusCode::PAYLOAD_TOO_LARGE => return Err(too_large(max_bytes)),
+                Err(_) => return Err(ApiError::bad_request("Error al leer el archivo")),
             };
 
             // 4.1 Validar tamaño
-            if data.len() > MAX_IMAGE_BYTES {
-                return (StatusCode::BAD_REQUEST, "La imagen excede el tamaño máximo de 5MB").into_response();
+            if data.len() > max_bytes {
+                return Err(too_large(max_bytes));
             }
 
             // 4.2 Validar MIME (solo imágenes comunes)
@@ -59,26 +66,50 @@ pub async fn upload_image_handler(mut multipart: Multipart) -> impl IntoResponse
                     ("image", "jpeg") | ("image", "png") | ("image", "webp") | ("image", "gif")
                 );
                 if !allowed {
-                    return (StatusCode::BAD_REQUEST, "Solo se permiten imágenes (jpg, png, webp, gif)").into_response();
+                    return Err(ApiError::new(
+                        StatusCode::BAD_REQUEST,
+                        "invalid_file_type",
+                        "Solo se permiten imágenes (jpg, png, webp, gif)",
+                    ));
                 }
             }
 
             // 5. Guardar en el disco duro
-            if let Err(e) = fs::write(&filepath, data).await {
+            if let Err(e) = fs::write(&filepath, &data).await {
                 tracing::error!("Error guardando imagen: {:?}", e);
-                return (StatusCode::INTERNAL_SERVER_ERROR, "No se pudo guardar la imagen").into_response();
+                return Err(ApiError::internal());
             }
 
+            metrics::record_upload(data.len());
+
             // 6. Devolver la URL pública
             // La URL será: http://localhost:3000/uploads/nombre-raro.jpg
             let public_url = format!("/uploads/{}", new_filename);
             
-            return (StatusCode::OK, Json(json!({ 
+            return Ok((StatusCode::OK, Json(json!({ 
                 "url": public_url,
                 "original_name": file_name 
-            }))).into_response();
+            }))));
         }
     }
 
-    (StatusCode::BAD_REQUEST, "No se envió ningún campo 'image'").into_response()
+    Err(ApiError::bad_request("No se envió ningún campo 'image'"))
+}
+
+fn too_large(max_bytes: usize) -> ApiError {
+    ApiError::new(
+        StatusCode::BAD_REQUEST,
+        "file_too_large",
+        format!("La imagen excede el tamaño máximo de {}", human_size(max_bytes)),
+    )
+}
+
+// 5242880 -> "5MB"; 1536000 -> "1500KB"
+fn human_size(bytes: usize) -> String {
+    const MB: usize = 1024 * 1024;
+    if bytes.is_multiple_of(MB) {
+        format!("{}MB", bytes / MB)
+    } else {
+        format!("{}KB", bytes.div_ceil(1024))
+    }
 }
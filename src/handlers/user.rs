@@ -0,0 +1,85 @@
+use axum::{
+    extract::{State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension,
+};
+use crate::{
+    db::DbPool,
+    error::{ApiError, ApiJson, ApiPath},
+    models::user::{Claims, UpdateRolePayload},
+    utils::{
+        audit::{self, AuditEntry},
+        login_guard,
+        request_info::ClientIp,
+    },
+};
+
+// POST /api/admin/users/:id/unlock (admin) - levantar el bloqueo por intentos fallidos
+pub async fn unlock_user_handler(
+    ApiPath(user_id): ApiPath<i64>,
+    State(pool): State<DbPool>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+) -> Result<impl IntoResponse, ApiError> {
+    let email = sqlx::query_scalar!("SELECT email FROM users WHERE id = $1", user_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(ApiError::db("Error buscando usuario"))?
+        .ok_or_else(|| ApiError::not_found("Usuario no encontrado"))?;
+
+    login_guard::clear_account(&pool, &email)
+        .await
+        .map_err(ApiError::db("Error desbloqueando usuario"))?;
+
+    tracing::info!("login_unlocked user_id={} by_admin={}", user_id, claims.user_id);
+    audit::record(
+        &pool,
+        AuditEntry::new("user.unlock", "user", user_id)
+            .actor(&claims)
+            .ip(&ip),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// PUT /api/admin/users/:id/role (users.manage) - aplica en la siguiente petición del usuario
+pub async fn update_user_role_handler(
+    ApiPath(user_id): ApiPath<i64>,
+    State(pool): State<DbPool>,
+    ClientIp(ip): ClientIp,
+    Extension(claims): Extension<Claims>,
+    ApiJson(payload): ApiJson<UpdateRolePayload>,
+) -> Result<impl IntoResponse, ApiError> {
+    // Evita que un admin se quite a sí mismo el acceso (y deje el sitio sin admins)
+    if user_id == claims.user_id {
+        return Err(ApiError::bad_request("No puedes cambiar tu propio rol"));
+    }
+
+    let previous = sqlx::query_scalar!(
+        r#"
+        UPDATE users u SET role = $2
+        FROM users old
+        WHERE u.id = $1 AND old.id = u.id
+        RETURNING old.role
+        "#,
+        user_id,
+        payload.role.as_str()
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(ApiError::db("Error cambiando rol"))?
+    .ok_or_else(|| ApiError::not_found("Usuario no encontrado"))?;
+
+    tracing::info!("user_role_changed user_id={} role={} by_admin={}", user_id, payload.role, claims.user_id);
+    audit::record(
+        &pool,
+        AuditEntry::new("user.role_change", "user", user_id)
+            .actor(&claims)
+            .ip(&ip)
+            .before(&serde_json::json!({ "role": previous }))
+            .after(&serde_json::json!({ "role": payload.role })),
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
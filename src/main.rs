@@ -1,42 +1,227 @@
+mod cli;
+mod config;
 mod db;
+mod error;
 mod models;
 mod handlers;
 mod routes;
+mod state;
 mod utils;
 
-use std::net::SocketAddr;
+use std::{future::IntoFuture, net::SocketAddr, sync::Arc, time::Duration};
+use clap::Parser;
 use tokio::net::TcpListener;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use tower_http::cors::{CorsLayer, Any};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
+use crate::{
+    cli::{Cli, Command},
+    config::AppConfig,
+    db::DbPool,
+    state::AppState,
+    utils::{bootstrap::SetupToken, metrics::Metrics, telemetry::Telemetry},
+};
 
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
 
+    // Subcomando (serve por defecto; ver cli.rs). Va antes que nada para que --help no necesite config
+    let command = Cli::parse().command();
+
+    let telemetry = init_logging();
+
+    // Configuración: se valida entera y, si algo falla, se listan todos los problemas. Lo que solo
+    // usa el servidor ([jwt]) solo se exige con serve: migrate, create-admin... no firman tokens
+    let config = match AppConfig::load(matches!(command, Command::Serve)) {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            tracing::error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // No necesita base: solo pregunta al servidor que ya corre
+    if matches!(command, Command::Healthcheck) {
+        if let Err(e) = cli::healthcheck(&config).await {
+            tracing::error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let pool = db::init_db(&config.database).await;
+    tracing::info!("✅ Conexión a Postgres exitosa");
+
+    let migrate_only = matches!(command, Command::Migrate);
+    if migrate_only || (matches!(command, Command::Serve) && config.database.migrate_on_start) {
+        if let Err(e) = db::migrations::run(&pool).await {
+            tracing::error!("Error aplicando migraciones: {}", e);
+            std::process::exit(1);
+        }
+        if migrate_only {
+            return;
+        }
+    }
+
+    // Esquema desactualizado: mejor no arrancar (ni tocar datos) que fallar en cada consulta
+    if let Err(problems) = db::migrations::check(&pool).await {
+        tracing::error!(
+            "El esquema no corresponde a este binario (aplicar con `ctv_backend migrate` o MIGRATE_ON_START=true):\n  - {}",
+            problems.join("\n  - ")
+        );
+        std::process::exit(1);
+    }
+
+    let result = match command {
+        Command::Serve => {
+            serve(config, pool.clone()).await;
+            Ok(())
+        }
+        // Ya se aplicaron arriba
+        Command::Migrate => Ok(()),
+        Command::CreateAdmin { email, username, password_stdin } => {
+            cli::create_admin(&pool, &email, username, password_stdin).await
+        }
+        Command::ResetPassword { email, password_stdin, reset_2fa } => {
+            cli::reset_password(&pool, &email, password_stdin, reset_2fa).await
+        }
+        // Resuelto antes de conectar a la base
+        Command::Healthcheck => Ok(()),
+        Command::Seed => cli::seed(&pool).await,
+        Command::ReindexSearch => cli::reindex_search(&pool).await,
+        Command::GcUploads { dry_run, min_age_hours } => cli::gc_uploads(&pool, &config, dry_run, min_age_hours).await,
+    };
+    // Lo último: las tareas de fondo ya terminaron (o se dieron por perdidas) en serve
+    pool.close().await;
+    if let Some(telemetry) = telemetry {
+        telemetry.shutdown();
+    }
+    if let Err(e) = result {
+        tracing::error!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+// Nivel con RUST_LOG (por defecto info) y formato con LOG_FORMAT: "text" (por defecto) o "json",
+// una línea por evento con los campos del span de la petición (request_id, route, user_id...).
+// Se lee del entorno y no de AppConfig para poder loguear los errores de configuración.
+// Con OTEL_EXPORTER_OTLP_ENDPOINT también se exportan trazas (ver utils::telemetry); el
+// Telemetry devuelto se cierra al salir para no perder el último lote.
+fn init_logging() -> Option<Telemetry> {
+    let filter = tracing_subscriber::EnvFilter::new(std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()));
+    let format = std::env::var("LOG_FORMAT").unwrap_or_default();
+    let fmt_layer = match format.as_str() {
+        "json" => tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+        _ => tracing_subscriber::fmt::layer().boxed(),
+    };
+    let telemetry = Telemetry::from_env();
+
     tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "debug".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
+        .with(fmt_layer.with_filter(filter))
+        .with(telemetry.as_ref().ok().and_then(Option::as_ref).map(Telemetry::layer))
         .init();
 
-    let pool = db::init_db().await;
-    tracing::info!("✅ Conexión a Postgres exitosa");
+    if !matches!(format.as_str(), "" | "text" | "json") {
+        tracing::warn!("LOG_FORMAT={} desconocido: se usa text", format);
+    }
+    match telemetry {
+        Ok(telemetry) => {
+            if telemetry.is_some() {
+                tracing::info!("Exportando trazas por OTLP");
+            }
+            telemetry
+        }
+        // Como un error de configuración: mejor no arrancar que perder las trazas sin avisar
+        Err(e) => {
+            tracing::error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn serve(config: Arc<AppConfig>, pool: DbPool) {
+    handlers::health::mark_started();
+
+    // Claves JWT: si faltan o son inválidas, mejor no arrancar
+    let jwt_keys = utils::jwt_keys::load(&config.jwt).unwrap_or_else(|e| panic!("Error cargando claves JWT: {}", e));
+
+    // Sin ningún admin, el primero solo se crea con token de configuración (o con create-admin)
+    let setup_token = match SetupToken::prepare(&pool, &config).await {
+        Ok(token) => token,
+        Err(e) => panic!("Error comprobando si existe un admin: {}", e),
+    };
 
-   
-    let cors = CorsLayer::new()
-        .allow_origin(Any) 
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let metrics = if config.metrics.enabled {
+        Metrics::install().unwrap_or_else(|e| panic!("Error instalando el recorder de métricas: {}", e))
+    } else {
+        Metrics::default()
+    };
 
-    let app = routes::create_routes(pool)
-        .layer(cors);
+    let addr = config.server.addr();
+    let metrics_addr = config.metrics.port.map(|port| SocketAddr::new(config.server.host, port));
+    let grace = Duration::from_secs(config.server.shutdown_timeout_secs);
+    let state = AppState { setup_token, metrics, ..AppState::new(pool, config, jwt_keys) };
+    let jobs = state.jobs.clone();
+    state.rate_limiter.start_sweeper(&jobs);
+    let app = routes::create_routes(state.clone());
+
+    // SIGTERM/SIGINT: empieza el apagado ordenado (ver más abajo y utils::jobs)
+    tokio::spawn({
+        let jobs = jobs.clone();
+        async move {
+            utils::shutdown::signal().await;
+            jobs.stop();
+        }
+    });
+
+    // /metrics en su propio puerto: se enlaza antes que la API para no arrancar a medias
+    if let Some(metrics_addr) = metrics_addr {
+        let listener = TcpListener::bind(metrics_addr).await.expect("Fallo al enlazar el puerto de métricas");
+        tracing::info!("📈 Métricas en http://{}/metrics", metrics_addr);
+        let stopping = jobs.stopping();
+        tokio::spawn(async move {
+            let server = axum::serve(listener, routes::create_metrics_routes(state)).with_graceful_shutdown(stopping);
+            if let Err(e) = server.await {
+                tracing::error!("Error en el listener de métricas: {}", e);
+            }
+        });
+    }
 
-    let puerto = std::env::var("PORT").unwrap_or("3000".to_string());
-    let addr: SocketAddr = format!("0.0.0.0:{}", puerto).parse().expect("Dirección IP/Puerto inválido");
-    
     tracing::info!("🚀 Servidor CTV corriendo en http://{}", addr);
 
     let listener = TcpListener::bind(addr).await.expect("Fallo al enlazar el puerto");
-    axum::serve(listener, app).await.unwrap();
-}
\ No newline at end of file
+    // ConnectInfo: necesitamos la IP del cliente para registrar sesiones
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(jobs.stopping())
+        .into_future();
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => {
+            // Sin señal: el listener falló
+            if let Err(e) = result {
+                tracing::error!("Error en el servidor HTTP: {}", e);
+            }
+            return;
+        }
+        _ = jobs.stopping() => {}
+    }
+
+    // Apagado: ya no se aceptan conexiones. Las peticiones en curso (uploads incluidos) y después
+    // las tareas de fondo tienen, entre todas, `grace` para terminar; lo que quede se corta
+    tracing::info!("shutdown_started grace_secs={}", grace.as_secs());
+    let deadline = tokio::time::Instant::now() + grace;
+    match tokio::time::timeout_at(deadline, server).await {
+        Ok(Err(e)) => tracing::error!("Error en el servidor HTTP: {}", e),
+        Ok(Ok(())) => {}
+        Err(_) => tracing::warn!("shutdown_timeout: se cortan las peticiones que seguían en curso"),
+    }
+    match jobs.drain(deadline).await {
+        0 => tracing::info!("shutdown_completed"),
+        pending => tracing::warn!("shutdown_timeout: {} tareas de fondo sin terminar", pending),
+    }
+}
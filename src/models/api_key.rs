@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use validator::Validate;
+use crate::utils::validation;
+
+// API key tal como se lista (sin hash)
+#[derive(Debug, Serialize, FromRow)]
+pub struct ApiKey {
+    pub id: i64,
+    pub name: String,
+    pub prefix: String,
+    pub permissions: Vec<String>,
+    pub created_by: i64,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateApiKeySchema {
+    #[validate(length(min = 1, max = 100, message = "Entre 1 y 100 caracteres"), custom(function = "validation::not_blank"))]
+    pub name: String,
+    #[validate(length(min = 1, message = "Indica al menos un permiso"))]
+    pub permissions: Vec<String>, // ej: ["articles.create", "articles.publish"]
+    #[validate(custom(function = "validation::future_date"))]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+// Respuesta de creación: la única vez que se ve la key completa
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub key: String,
+}
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use sqlx::types::Json;
 use chrono::{DateTime, Utc};
+use validator::{Validate, ValidationError};
+use crate::{models::author::AuthorSummary, utils::validation};
 
 // 1. Estructura que representa una fila completa en la Base de Datos
 #[derive(Debug, Deserialize, Serialize, FromRow)]
@@ -21,19 +24,83 @@ pub struct Article {
     pub published_at: Option<DateTime<Utc>>,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
+    pub author: Option<Json<AuthorSummary>>, // Byline público (None si el autor fue borrado)
+    pub contributors: Json<Vec<Contributor>>, // Créditos en orden (article_contributors)
 }
 
+// Función de cada persona acreditada en la pieza
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContributorRole {
+    Author,
+    Photographer,
+    Camera,
+    Editor,
+}
+
+impl ContributorRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContributorRole::Author => "author",
+            ContributorRole::Photographer => "photographer",
+            ContributorRole::Camera => "camera",
+            ContributorRole::Editor => "editor",
+        }
+    }
+}
+
+// Crédito tal como se muestra (usuario con perfil o externo solo con nombre)
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Contributor {
+    pub user_id: Option<i64>,
+    pub slug: Option<String>,
+    pub name: String,
+    pub photo_url: Option<String>,
+    pub role: ContributorRole,
+}
+
+// Crédito recibido al crear/editar: user_id (redacción) o name (externo), uno de los dos
+#[derive(Debug, Deserialize, Serialize, Validate)]
+#[validate(schema(function = "user_or_name"))]
+pub struct ContributorInput {
+    pub user_id: Option<i64>,
+    #[validate(length(max = 100, message = "Máximo 100 caracteres"))]
+    pub name: Option<String>,
+    pub role: ContributorRole,
+}
+
+fn user_or_name(c: &ContributorInput) -> Result<(), ValidationError> {
+    let has_name = c.name.as_deref().is_some_and(|n| !n.trim().is_empty());
+    if c.user_id.is_some() == has_name {
+        return Err(ValidationError::new("user_or_name")
+            .with_message("Cada crédito lleva user_id o name (uno de los dos)".into()));
+    }
+    Ok(())
+}
+
+// Máximo de créditos por pieza (evita payloads absurdos)
+pub const MAX_CONTRIBUTORS: u64 = 20;
+
 // 2. Estructura para recibir los datos del Frontend (JSON) al crear una noticia
-#[derive(Debug, Deserialize)]
+// (que category_id y los user_id de los créditos existan se comprueba en el handler)
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateArticleSchema {
+    #[validate(length(max = 255, message = "Máximo 255 caracteres"), custom(function = "validation::not_blank"))]
     pub title: String,
+    #[validate(length(max = 200000, message = "Máximo 200000 caracteres"), custom(function = "validation::not_blank"))]
     pub content: String,
+    #[validate(length(max = 500, message = "Máximo 500 caracteres"))]
     pub excerpt: Option<String>,
     pub category_id: Option<i32>,
+    #[validate(length(max = 500, message = "Máximo 500 caracteres"), custom(function = "validation::image_url"))]
     pub main_image_url: Option<String>,
+    #[validate(length(max = 500, message = "Máximo 500 caracteres"), custom(function = "validation::video_url"))]
     pub video_embed_url: Option<String>,
+    #[validate(custom(function = "validation::article_status"))]
     pub status: Option<String>,       // draft | published | archived
     pub is_featured: Option<bool>,
     pub is_breaking: Option<bool>,
     pub published_at: Option<DateTime<Utc>>,
+    #[validate(length(max = "MAX_CONTRIBUTORS", message = "Demasiados créditos (máx. 20)"), nested)]
+    pub contributors: Option<Vec<ContributorInput>>, // Por defecto: quien la crea, como author
 }
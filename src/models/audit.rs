@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// Fila de audit_log tal como la devuelve GET /api/admin/audit
+#[derive(Debug, Serialize, FromRow)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub actor_id: Option<i64>,
+    pub actor_email: Option<String>,
+    pub api_key_id: Option<i64>,
+    pub action: String,
+    pub entity_type: String,
+    pub entity_id: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Filtros de GET /api/admin/audit (todos opcionales)
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub actor_id: Option<i64>,
+    pub action: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditPage {
+    pub entries: Vec<AuditLogEntry>,
+    pub page: i64,
+    pub per_page: i64,
+}
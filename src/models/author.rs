@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use std::collections::BTreeMap;
+use validator::Validate;
+use crate::{models::article::Article, utils::validation};
+
+// Byline embebido en cada Article (solo datos públicos: nunca email ni hash)
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AuthorSummary {
+    pub id: i64,
+    pub slug: Option<String>, // Sin slug no hay página de autor
+    pub display_name: String, // display_name o, si no lo ha puesto, el username
+    pub photo_url: Option<String>,
+}
+
+// Perfil público (GET /api/authors/:slug y GET/PUT /api/me/profile)
+#[derive(Debug, Serialize)]
+pub struct AuthorProfile {
+    pub id: i64,
+    pub slug: Option<String>,
+    pub display_name: String,
+    pub bio: Option<String>,
+    pub photo_url: Option<String>,
+    pub social_links: Json<BTreeMap<String, String>>, // {"x": "https://...", "instagram": "..."}
+}
+
+// Lo que recibimos para editar el perfil propio (campos ausentes no se tocan)
+// Todo se muestra en páginas públicas: las URLs solo pueden ser http(s) (o /uploads/ en la foto)
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateProfilePayload {
+    #[validate(length(max = 80, message = "Máximo 80 caracteres"), custom(function = "validation::slug"))]
+    pub slug: Option<String>,
+    #[validate(length(max = 120, message = "Máximo 120 caracteres"), custom(function = "validation::not_blank"))]
+    pub display_name: Option<String>,
+    #[validate(length(max = 2000, message = "Máximo 2000 caracteres"))]
+    pub bio: Option<String>,
+    #[validate(length(max = 500, message = "Máximo 500 caracteres"), custom(function = "validation::image_url"))]
+    pub photo_url: Option<String>,
+    #[validate(custom(function = "validation::social_links"))]
+    pub social_links: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthorPageQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+// Página de autor: perfil + sus noticias publicadas paginadas
+#[derive(Debug, Serialize)]
+pub struct AuthorPage {
+    pub author: AuthorProfile,
+    pub articles: Vec<Article>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Fail,
+    Timeout,
+}
+
+// Resultado de una comprobación (database, pool, migrations, uploads)
+#[derive(Debug, Serialize)]
+pub struct ComponentHealth {
+    pub status: CheckStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+// GET /readyz: es público, así que solo el estado de cada pieza (sin errores ni detalles)
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    pub status: &'static str, // ready | not_ready
+    pub checks: BTreeMap<&'static str, CheckStatus>,
+}
+
+// GET /api/admin/health
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    pub status: &'static str,
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: i64,
+    pub components: BTreeMap<&'static str, ComponentHealth>,
+}
@@ -3,3 +3,10 @@ pub mod category;
 pub mod user;
 pub mod tag;
 pub mod site_config;
+pub mod session;
+pub mod two_factor;
+pub mod role;
+pub mod api_key;
+pub mod author;
+pub mod audit;
+pub mod health;
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+// Roles de la redacción (columna users.role)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    ChiefEditor, // Publica y edita noticias de cualquiera
+    Editor,      // Publica y edita las suyas
+    Contributor, // Solo borradores propios
+}
+
+// Permisos con nombre estable ("articles.publish"), los que aparecen en los 403
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ArticlesCreate,
+    ArticlesEditOwn,
+    ArticlesEditAny,
+    ArticlesPublish,
+    ArticlesDelete,
+    TagsManage,
+    SiteConfigUpdate,
+    UsersManage,
+    UploadsCreate,
+    ApiKeysManage,
+    AuditRead,
+    HealthRead,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 12] = [
+        Permission::ArticlesCreate,
+        Permission::ArticlesEditOwn,
+        Permission::ArticlesEditAny,
+        Permission::ArticlesPublish,
+        Permission::ArticlesDelete,
+        Permission::TagsManage,
+        Permission::SiteConfigUpdate,
+        Permission::UsersManage,
+        Permission::UploadsCreate,
+        Permission::ApiKeysManage,
+        Permission::AuditRead,
+        Permission::HealthRead,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ArticlesCreate => "articles.create",
+            Permission::ArticlesEditOwn => "articles.edit_own",
+            Permission::ArticlesEditAny => "articles.edit_any",
+            Permission::ArticlesPublish => "articles.publish",
+            Permission::ArticlesDelete => "articles.delete",
+            Permission::TagsManage => "tags.manage",
+            Permission::SiteConfigUpdate => "site_config.update",
+            Permission::UsersManage => "users.manage",
+            Permission::UploadsCreate => "uploads.create",
+            Permission::ApiKeysManage => "api_keys.manage",
+            Permission::AuditRead => "audit.read",
+            Permission::HealthRead => "health.read",
+        }
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Permission::ALL
+            .into_iter()
+            .find(|p| p.as_str() == s)
+            .ok_or_else(|| format!("Permiso desconocido: {}", s))
+    }
+}
+
+impl Role {
+    // Matriz de permisos: única fuente de verdad de quién puede qué
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::Admin => &[
+                ArticlesCreate, ArticlesEditOwn, ArticlesEditAny, ArticlesPublish, ArticlesDelete,
+                TagsManage, SiteConfigUpdate, UsersManage, UploadsCreate, ApiKeysManage, AuditRead,
+                HealthRead,
+            ],
+            Role::ChiefEditor => &[
+                ArticlesCreate, ArticlesEditOwn, ArticlesEditAny, ArticlesPublish,
+                TagsManage, UploadsCreate,
+            ],
+            Role::Editor => &[ArticlesCreate, ArticlesEditOwn, ArticlesPublish, UploadsCreate],
+            Role::Contributor => &[ArticlesCreate, ArticlesEditOwn, UploadsCreate],
+        }
+    }
+
+    pub fn has(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::ChiefEditor => "chief_editor",
+            Role::Editor => "editor",
+            Role::Contributor => "contributor",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "admin" => Ok(Role::Admin),
+            "chief_editor" => Ok(Role::ChiefEditor),
+            "editor" => Ok(Role::Editor),
+            "contributor" => Ok(Role::Contributor),
+            other => Err(format!("Rol desconocido: {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
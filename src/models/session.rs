@@ -0,0 +1,16 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+// Sesión activa tal como la ve el usuario (nunca exponemos el user_id ajeno)
+#[derive(Debug, Serialize, FromRow)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub is_current: bool, // true si es la sesión del token que hace la petición
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use validator::Validate;
+use crate::utils::validation;
 
 #[derive(Debug, Deserialize, Serialize, FromRow)]
 pub struct SiteConfig {
@@ -9,9 +11,11 @@ pub struct SiteConfig {
     pub breaking_news_banner: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateSiteConfigSchema {
+    #[validate(length(max = 500, message = "Máximo 500 caracteres"), custom(function = "validation::video_url"))]
     pub live_stream_url: Option<String>,
     pub is_live_active: Option<bool>,
+    #[validate(length(max = 280, message = "Máximo 280 caracteres"))]
     pub breaking_news_banner: Option<String>,
 }
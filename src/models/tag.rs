@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use validator::Validate;
+use crate::utils::validation;
 
 #[derive(Debug, Deserialize, Serialize, FromRow)]
 pub struct Tag {
@@ -8,8 +10,10 @@ pub struct Tag {
     pub slug: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateTagSchema {
+    #[validate(length(max = 100, message = "Máximo 100 caracteres"), custom(function = "validation::not_blank"))]
     pub name: String,
-    pub slug: Option<String>,
+    #[validate(length(max = 100, message = "Máximo 100 caracteres"), custom(function = "validation::slug"))]
+    pub slug: Option<String>, // Por defecto, el nombre normalizado
 }
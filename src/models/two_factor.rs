@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+// Token temporal que emite el login cuando falta el segundo factor.
+// No sirve como token de sesión: no lleva `sid` ni `role`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengeClaims {
+    pub sub: String, // email
+    pub exp: usize,
+    pub iat: usize,
+    pub user_id: i64,
+    pub purpose: String, // "2fa_verify" | "2fa_enroll"
+}
+
+// Respuesta del login cuando hay que completar el 2FA en /api/auth/2fa/verify
+#[derive(Debug, Serialize)]
+pub struct ChallengeResponse {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+    pub expires_in: i64,
+    // Solo si el 2FA es obligatorio y aún no está activado: hay que enrolarse primero
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub otpauth_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyChallengePayload {
+    pub challenge_token: String,
+    pub code: String, // código TOTP de 6 dígitos o código de recuperación
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCodePayload {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorSetupResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorRequiredPayload {
+    pub required: bool,
+}
@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+use crate::{
+    models::role::{Permission, Role},
+    utils::validation,
+};
 
 // Lo que guardamos en la base de datos
 #[derive(Debug, Serialize, FromRow)]
@@ -10,6 +16,8 @@ pub struct User {
     #[serde(skip)] // ¡Jamás envíes el hash de la contraseña en el JSON!
     pub password_hash: String,
     pub role: String,
+    pub totp_enabled: bool,
+    pub totp_required: bool, // 2FA impuesto por un admin
 }
 
 // Lo que recibimos para hacer Login
@@ -20,11 +28,21 @@ pub struct LoginPayload {
 }
 
 // Lo que recibimos para Registrar un usuario (solo para uso interno inicial)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct RegisterPayload {
+    #[validate(length(min = 3, max = 100, message = "Entre 3 y 100 caracteres"))]
     pub username: String,
+    #[validate(email(message = "Email inválido"), length(max = 255, message = "Máximo 255 caracteres"))]
     pub email: String,
+    #[validate(length(max = 128, message = "Máximo 128 caracteres"), custom(function = "validation::password_policy"))]
     pub password: String,
+    pub role: Option<Role>, // Por defecto: editor
+}
+
+// Cambio de rol (admin)
+#[derive(Debug, Deserialize)]
+pub struct UpdateRolePayload {
+    pub role: Role,
 }
 
 // Lo que devolvemos cuando el login es exitoso
@@ -32,6 +50,9 @@ pub struct RegisterPayload {
 pub struct AuthResponse {
     pub token: String,
     pub token_type: String,
+    // Solo al terminar el enrolamiento 2FA desde el login: se muestran una única vez
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
 }
 
 // Lo que viaja DENTRO del token encriptado (Claims)
@@ -41,5 +62,12 @@ pub struct Claims {
     pub exp: usize,  // Expiración
     pub iat: usize,  // Issued At
     pub user_id: i64,
-    pub role: String,
+    pub role: Role,
+    pub sid: Uuid, // Sesión a la que pertenece el token (user_sessions.id)
+    // Solo peticiones con X-API-Key: id de la key y permisos a los que está limitada.
+    // Nunca viajan dentro de un JWT.
+    #[serde(skip)]
+    pub api_key_id: Option<i64>,
+    #[serde(skip)]
+    pub scopes: Option<Vec<Permission>>,
 }
@@ -1,21 +1,37 @@
 use axum::{
+    extract::DefaultBodyLimit,
     middleware,
     routing::{get, post, delete, put},
     Router,
 };
-use tower_http::services::ServeDir;
+use tower_http::{services::ServeDir, trace::TraceLayer};
 use crate::{
-    db::DbPool, 
-    handlers::{article, category, auth, upload, site_config, tag},
-    utils::jwt::{auth_middleware, admin_middleware} // <--- Importamos ambos middlewares
+    error::ApiError,
+    handlers::{api_key, article, audit, author, category, auth, health, jwks, metrics, oidc, upload, site_config, tag, session, two_factor, user},
+    models::role::Permission,
+    utils::{
+        jwt::{auth_middleware, session_middleware},
+        permissions::require_permission,
+        http_security::{cors_layer, security_headers},
+        http_trace,
+        metrics::track_http,
+        rate_limit::{rate_limit, RateGroup},
+        request_id::request_id_middleware,
+    },
+    state::AppState,
 };
 
-pub fn create_routes(pool: DbPool) -> Router {
+pub fn create_routes(state: AppState) -> Router {
+    // Capa que exige un permiso concreto (ver models::role para la matriz por rol)
+    let require = |permission: Permission| middleware::from_fn_with_state(permission, require_permission);
+
+    // Límite de peticiones por grupo de rutas (ver utils::rate_limit y [rate_limit])
+    let limit = |group: RateGroup| middleware::from_fn_with_state((state.rate_limiter.clone(), group), rate_limit);
+
     // 1. Rutas Públicas (Todo el mundo)
-    let public_routes = Router::new()
+    // Lecturas: con ?search= cuentan en el grupo de búsqueda
+    let public_reads = Router::new()
         .route("/api/categories", get(category::list_categories_handler))
-        .route("/api/auth/register", post(auth::register_handler))
-        .route("/api/auth/login", post(auth::login_handler))
         .route("/api/articles", get(article::list_articles_handler))
         .route("/api/articles/most-read", get(article::most_read_handler))
         .route("/api/articles/featured", get(article::featured_handler))
@@ -23,32 +39,117 @@ pub fn create_routes(pool: DbPool) -> Router {
         .route("/api/articles/videos", get(article::videos_handler))
         .route("/api/articles/:slug/related", get(article::related_handler))
         .route("/api/articles/:slug", get(article::get_article_handler))
-        .route("/api/articles/:slug/view", post(article::increment_views_handler))
         .route("/api/articles/:slug/tags", get(tag::list_article_tags_handler))
+        .route("/api/authors/:slug", get(author::get_author_handler))
         .route("/api/site-config", get(site_config::get_site_config_handler))
         .route("/api/tags", get(tag::list_tags_handler))
-        .route("/healthz", get(crate::handlers::health::health_handler))
-        .nest_service("/uploads", ServeDir::new("uploads"));
-
-    // 2. Rutas de Editores (Crear, Editar, Subir Foto) - Requieren Auth Básico
-    let editor_routes = Router::new()
-        .route("/api/articles", post(article::create_article_handler))
-        .route("/api/admin/articles/:id", put(article::update_article_handler)) // Editar sí dejamos a editores
-        .route("/api/upload", post(upload::upload_image_handler))
-        .route_layer(middleware::from_fn(auth_middleware));
-
-    // 3. Rutas de ADMIN (Borrar) - Requieren Auth de Admin
-    let admin_routes = Router::new()
-        .route("/api/admin/articles/:id", delete(article::delete_article_handler))
-        .route("/api/admin/site-config", put(site_config::update_site_config_handler))
-        .route("/api/admin/tags", post(tag::create_tag_handler))
-        .route("/api/admin/articles/:id/tags", post(tag::set_article_tags_handler))
-        .route_layer(middleware::from_fn(admin_middleware));
-
-    // Fusionamos todo
+        .route_layer(limit(RateGroup::PublicRead));
+
+    // Entrada a la cuenta: lo que un atacante probaría en bucle
+    let auth_routes = Router::new()
+        .route("/api/auth/register", post(auth::register_handler))
+        .route("/api/auth/login", post(auth::login_handler))
+        .route("/api/auth/2fa/verify", post(two_factor::verify_challenge_handler))
+        .route("/api/auth/oidc/login", get(oidc::oidc_login_handler))
+        .route("/api/auth/oidc/callback", get(oidc::oidc_callback_handler))
+        .route_layer(limit(RateGroup::Auth));
+
+    // Sondas, JWKS e imágenes quedan fuera del límite
+    let mut public_routes = Router::new()
+        .merge(public_reads)
+        .merge(auth_routes)
+        .route("/api/articles/:slug/view", post(article::increment_views_handler).route_layer(limit(RateGroup::Views)))
+        .route("/healthz", get(health::health_handler))
+        .route("/readyz", get(health::readiness_handler))
+        .route("/.well-known/jwks.json", get(jwks::jwks_handler))
+        .nest_service("/uploads", ServeDir::new(&state.config.uploads.dir));
+    // Con [metrics] port, /metrics solo se sirve en ese listener (ver create_metrics_routes)
+    if state.config.metrics.port.is_none() {
+        public_routes = public_routes.route("/metrics", get(metrics::metrics_handler));
+    }
+
+    // 2. Cuenta propia: solo con JWT de sesión (cualquier rol)
+    let account_routes = Router::new()
+        .route("/api/me/profile", get(author::get_my_profile_handler).put(author::update_my_profile_handler))
+        .route("/api/me/sessions", get(session::list_my_sessions_handler))
+        .route("/api/me/sessions/:id", delete(session::revoke_my_session_handler))
+        .route("/api/me/2fa/setup", post(two_factor::setup_handler))
+        .route("/api/me/2fa/enable", post(two_factor::enable_handler))
+        .route("/api/me/2fa/disable", post(two_factor::disable_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), session_middleware));
+
+    let upload_body_limit = state.config.uploads.max_image_bytes + 64 * 1024;
+
+    // 3. Rutas autenticadas (JWT o X-API-Key): cada una declara el permiso que necesita
+    let protected_routes = Router::new()
+        // Redacción (edit_own/edit_any/publish se resuelven dentro del handler según la noticia)
+        .route("/api/articles", post(article::create_article_handler).route_layer(require(Permission::ArticlesCreate)))
+        .route(
+            "/api/admin/articles/:id",
+            put(article::update_article_handler)
+                .merge(delete(article::delete_article_handler).route_layer(require(Permission::ArticlesDelete))),
+        )
+        .route(
+            "/api/upload",
+            post(upload::upload_image_handler)
+                // Por usuario: va detrás de auth_middleware y de require (la capa de fuera corre
+                // primero), así que sin uploads.create no se gasta cupo
+                .route_layer(limit(RateGroup::Uploads))
+                .route_layer(require(Permission::UploadsCreate))
+                // El límite por defecto de axum (2MB) cortaría antes que el nuestro; margen para el multipart
+                .layer(DefaultBodyLimit::max(upload_body_limit)),
+        )
+        .route("/api/admin/tags", post(tag::create_tag_handler).route_layer(require(Permission::TagsManage)))
+        .route("/api/admin/articles/:id/tags", post(tag::set_article_tags_handler).route_layer(require(Permission::TagsManage)))
+        .route("/api/admin/site-config", put(site_config::update_site_config_handler).route_layer(require(Permission::SiteConfigUpdate)))
+        // Gestión de usuarios
+        .route("/api/admin/users/:id/role", put(user::update_user_role_handler).route_layer(require(Permission::UsersManage)))
+        .route("/api/admin/users/:id/sessions", delete(session::revoke_user_sessions_handler).route_layer(require(Permission::UsersManage)))
+        .route("/api/admin/users/:id/unlock", post(user::unlock_user_handler).route_layer(require(Permission::UsersManage)))
+        .route(
+            "/api/admin/users/:id/2fa",
+            put(two_factor::set_user_2fa_required_handler)
+                .delete(two_factor::reset_user_2fa_handler)
+                .route_layer(require(Permission::UsersManage)),
+        )
+        // Integraciones
+        .route(
+            "/api/admin/api-keys",
+            get(api_key::list_api_keys_handler)
+                .post(api_key::create_api_key_handler)
+                .route_layer(require(Permission::ApiKeysManage)),
+        )
+        .route("/api/admin/api-keys/:id", delete(api_key::revoke_api_key_handler).route_layer(require(Permission::ApiKeysManage)))
+        // Auditoría y estado del servicio
+        .route("/api/admin/audit", get(audit::list_audit_handler).route_layer(require(Permission::AuditRead)))
+        .route("/api/admin/health", get(health::health_report_handler).route_layer(require(Permission::HealthRead)))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
+
+    // Fusionamos todo. Capas (de fuera hacia dentro): cabeceras de seguridad y CORS, que cubren
+    // también preflights y errores; request id, para que cualquier error y el span lo lleven; el
+    // span por petición (http_trace) y las métricas HTTP, ambos con la plantilla de ruta ya resuelta
     Router::new()
         .merge(public_routes)
-        .merge(editor_routes)
-        .merge(admin_routes)
-        .with_state(pool)
+        .merge(account_routes)
+        .merge(protected_routes)
+        .fallback(|| async { ApiError::not_found("Ruta no encontrada") })
+        .layer(middleware::from_fn(track_http))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(http_trace::make_span)
+                .on_response(http_trace::on_response),
+        )
+        .layer(middleware::from_fn(request_id_middleware))
+        .layer(cors_layer(&state.config.cors))
+        .layer(middleware::from_fn_with_state(state.config.clone(), security_headers))
+        .with_state(state)
+}
+
+// Listener aparte para el scrape de Prometheus ([metrics] port): típicamente solo accesible desde
+// la red interna, mientras el puerto de la API queda expuesto
+pub fn create_metrics_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics::metrics_handler))
+        .fallback(|| async { ApiError::not_found("Ruta no encontrada") })
+        .with_state(state)
 }
@@ -0,0 +1,113 @@
+use axum::extract::FromRef;
+use std::sync::Arc;
+use crate::{
+    config::AppConfig,
+    db::DbPool,
+    utils::{
+        bootstrap::SetupToken, cache::ResponseCache, jobs::Jobs, jwt_keys::JwtKeys, metrics::Metrics,
+        oidc::OidcClient, rate_limit::RateLimiter, request_info::TrustedProxies,
+    },
+};
+
+// Estado compartido por todos los handlers. Cada uno extrae solo la pieza que necesita
+// (`State<DbPool>`, `State<Arc<AppConfig>>`, `State<OidcClient>`, `State<Jobs>`...) gracias a los
+// FromRef de abajo. Los campos son públicos para poder montar el router con piezas falsas:
+//   AppState { oidc: cliente_de_pruebas, ..AppState::new(pool, config, keys) }
+// Un componente compartido nuevo (caché, almacenamiento, correo...) se añade aquí con su FromRef.
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: DbPool,
+    pub config: Arc<AppConfig>,
+    // Firma y verificación de nuestros JWT (ver utils::jwt_keys)
+    pub jwt_keys: Arc<JwtKeys>,
+    pub oidc: OidcClient,
+    pub jobs: Jobs,
+    // Vacío salvo que falte el primer admin (ver serve en main.rs)
+    pub setup_token: SetupToken,
+    // Sin recorder salvo que serve lo instale (ver utils::metrics)
+    pub metrics: Metrics,
+    // Cubos de utils::rate_limit, compartidos por todas las rutas
+    pub rate_limiter: RateLimiter,
+    // Respuestas públicas de portada (ver utils::cache)
+    pub cache: ResponseCache,
+    // De quién creemos X-Forwarded-For (ver utils::request_info::ClientIp)
+    pub trusted_proxies: TrustedProxies,
+}
+
+impl AppState {
+    pub fn new(pool: DbPool, config: Arc<AppConfig>, jwt_keys: JwtKeys) -> AppState {
+        let trusted_proxies = TrustedProxies::new(&config.server.trusted_proxies);
+        AppState {
+            rate_limiter: RateLimiter::new(&config.rate_limit, trusted_proxies.clone()),
+            cache: ResponseCache::new(&config.cache),
+            trusted_proxies,
+            jwt_keys: Arc::new(jwt_keys),
+            pool,
+            config,
+            oidc: OidcClient::new(),
+            jobs: Jobs::new(),
+            setup_token: SetupToken::default(),
+            metrics: Metrics::default(),
+        }
+    }
+}
+
+impl FromRef<AppState> for DbPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<AppConfig> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<JwtKeys> {
+    fn from_ref(state: &AppState) -> Self {
+        state.jwt_keys.clone()
+    }
+}
+
+impl FromRef<AppState> for OidcClient {
+    fn from_ref(state: &AppState) -> Self {
+        state.oidc.clone()
+    }
+}
+
+impl FromRef<AppState> for Jobs {
+    fn from_ref(state: &AppState) -> Self {
+        state.jobs.clone()
+    }
+}
+
+impl FromRef<AppState> for SetupToken {
+    fn from_ref(state: &AppState) -> Self {
+        state.setup_token.clone()
+    }
+}
+
+impl FromRef<AppState> for Metrics {
+    fn from_ref(state: &AppState) -> Self {
+        state.metrics.clone()
+    }
+}
+
+impl FromRef<AppState> for RateLimiter {
+    fn from_ref(state: &AppState) -> Self {
+        state.rate_limiter.clone()
+    }
+}
+
+impl FromRef<AppState> for ResponseCache {
+    fn from_ref(state: &AppState) -> Self {
+        state.cache.clone()
+    }
+}
+
+impl FromRef<AppState> for TrustedProxies {
+    fn from_ref(state: &AppState) -> Self {
+        state.trusted_proxies.clone()
+    }
+}
@@ -0,0 +1,84 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use crate::{
+    db::DbPool,
+    error::ApiError,
+    models::{role::{Permission, Role}, user::Claims},
+};
+
+// Formato: ctv_<prefijo>_<secreto>. El prefijo identifica la key en listados y logs.
+const KEY_PREFIX: &str = "ctv";
+
+fn random_hex(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buf);
+    buf.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Devuelve (prefijo, key completa)
+pub fn generate() -> (String, String) {
+    let prefix = random_hex(4);
+    let key = format!("{}_{}_{}", KEY_PREFIX, prefix, random_hex(24));
+    (prefix, key)
+}
+
+pub fn hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Valida la key del header X-API-Key y construye los Claims con los que actúa:
+// identidad y rol de quien la creó, limitados a los permisos de la key
+pub async fn authenticate(pool: &DbPool, key: &str) -> Result<Claims, ApiError> {
+    let prefix = match key.split('_').collect::<Vec<_>>().as_slice() {
+        [KEY_PREFIX, prefix, _secret] => prefix.to_string(),
+        _ => return Err(invalid_key()),
+    };
+
+    let row = sqlx::query!(
+        r#"
+        UPDATE api_keys k
+        SET last_used_at = NOW()
+        FROM users u
+        WHERE k.prefix = $1 AND k.key_hash = $2 AND u.id = k.created_by
+          AND k.revoked_at IS NULL AND (k.expires_at IS NULL OR k.expires_at > NOW())
+        RETURNING k.id, k.permissions, u.id as user_id, u.email, u.role
+        "#,
+        prefix,
+        hash(key)
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(ApiError::db("Error verificando API key"))?
+    .ok_or_else(invalid_key)?;
+
+    let role = row.role.parse::<Role>().map_err(|e| {
+        tracing::error!("Usuario {} con rol inválido: {}", row.user_id, e);
+        ApiError::forbidden("Rol de usuario inválido")
+    })?;
+
+    // Permisos desconocidos (p. ej. de una versión anterior) simplemente se ignoran
+    let scopes = row
+        .permissions
+        .iter()
+        .filter_map(|p| p.parse::<Permission>().ok())
+        .collect();
+
+    Ok(Claims {
+        sub: row.email,
+        exp: 0,
+        iat: 0,
+        user_id: row.user_id,
+        role,
+        sid: Uuid::nil(),
+        api_key_id: Some(row.id),
+        scopes: Some(scopes),
+    })
+}
+
+fn invalid_key() -> ApiError {
+    ApiError::unauthorized("API key inválida, revocada o expirada")
+}
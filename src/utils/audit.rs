@@ -0,0 +1,90 @@
+use serde::Serialize;
+use serde_json::Value;
+use crate::{db::DbPool, models::user::Claims};
+
+// Entrada de audit_log. Se arma encadenando:
+//   AuditEntry::new("article.update", "article", id).actor(&claims).ip(ip).before(&old).after(&new)
+// y se guarda con record().
+pub struct AuditEntry {
+    action: &'static str,
+    entity_type: &'static str,
+    entity_id: Option<String>,
+    actor_id: Option<i64>,
+    actor_email: Option<String>,
+    api_key_id: Option<i64>,
+    before: Option<Value>,
+    after: Option<Value>,
+    ip: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(action: &'static str, entity_type: &'static str, entity_id: impl ToString) -> Self {
+        AuditEntry {
+            action,
+            entity_type,
+            entity_id: Some(entity_id.to_string()),
+            actor_id: None,
+            actor_email: None,
+            api_key_id: None,
+            before: None,
+            after: None,
+            ip: None,
+        }
+    }
+
+    // Usuario (y API key, si la hubo) que ejecuta la acción
+    pub fn actor(mut self, claims: &Claims) -> Self {
+        self.actor_id = Some(claims.user_id);
+        self.actor_email = Some(claims.sub.clone());
+        self.api_key_id = claims.api_key_id;
+        self
+    }
+
+    // Para logins, donde aún no hay Claims: id si se conoce y el email intentado
+    pub fn login_actor(mut self, user_id: Option<i64>, email: &str) -> Self {
+        self.actor_id = user_id;
+        self.actor_email = Some(email.to_string());
+        self
+    }
+
+    pub fn ip(mut self, ip: impl Into<String>) -> Self {
+        self.ip = Some(ip.into());
+        self
+    }
+
+    pub fn before<T: Serialize>(mut self, value: &T) -> Self {
+        self.before = serde_json::to_value(value).ok();
+        self
+    }
+
+    pub fn after<T: Serialize>(mut self, value: &T) -> Self {
+        self.after = serde_json::to_value(value).ok();
+        self
+    }
+}
+
+// Guarda la entrada. Si falla solo lo registramos en el log: la acción ya ocurrió
+// y no queremos devolver error al usuario por ello.
+pub async fn record(pool: &DbPool, entry: AuditEntry) {
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO audit_log (actor_id, actor_email, api_key_id, action, entity_type, entity_id, before, after, ip_address)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        entry.actor_id,
+        entry.actor_email,
+        entry.api_key_id,
+        entry.action,
+        entry.entity_type,
+        entry.entity_id,
+        entry.before,
+        entry.after,
+        entry.ip
+    )
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        tracing::error!("Error guardando auditoría {} {:?}: {:?}", entry.action, entry.entity_id, e);
+    }
+}
@@ -0,0 +1,54 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use std::sync::Arc;
+use crate::{config::AppConfig, db::DbPool, utils::security::constant_time_eq};
+
+pub const SETUP_TOKEN_HEADER: &str = "x-setup-token";
+
+// Token de un solo uso para crear el primer admin por HTTP (POST /api/auth/register con
+// X-Setup-Token). Solo existe mientras no haya ningún admin: el de SETUP_TOKEN o, si no está
+// definido, uno aleatorio que se muestra en el log al arrancar. La alternativa es `create-admin`.
+#[derive(Clone, Default)]
+pub struct SetupToken(Option<Arc<str>>);
+
+impl SetupToken {
+    pub async fn prepare(pool: &DbPool, config: &AppConfig) -> Result<SetupToken, sqlx::Error> {
+        if admin_exists(pool).await? {
+            return Ok(SetupToken(None));
+        }
+
+        let token = match &config.auth.setup_token {
+            Some(token) => {
+                tracing::warn!("bootstrap_pending: no hay ningún admin; se puede crear con el SETUP_TOKEN configurado");
+                token.clone()
+            }
+            None => {
+                let mut buf = [0u8; 24];
+                OsRng.fill_bytes(&mut buf);
+                let token = URL_SAFE_NO_PAD.encode(buf);
+                tracing::warn!(
+                    "bootstrap_pending: no hay ningún admin. Crear con `ctv_backend create-admin` o con \
+                     POST /api/auth/register y el header X-Setup-Token: {}",
+                    token
+                );
+                token
+            }
+        };
+        Ok(SetupToken(Some(token.into())))
+    }
+
+    // Comparación en tiempo constante
+    pub fn matches(&self, candidate: &str) -> bool {
+        let Some(token) = &self.0 else { return false };
+        constant_time_eq(token.as_bytes(), candidate.as_bytes())
+    }
+}
+
+pub async fn admin_exists<'e, E>(executor: E) -> Result<bool, sqlx::Error>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_scalar!(r#"SELECT EXISTS (SELECT 1 FROM users WHERE role = 'admin') AS "exists!""#)
+        .fetch_one(executor)
+        .await
+}
@@ -0,0 +1,208 @@
+use axum::{
+    body::Bytes,
+    http::{header, HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use crate::{config::CacheConfig, error::ApiError, utils::metrics};
+
+// Caché en memoria de las respuestas públicas más pedidas (portada: destacadas, breaking, más
+// leídas, vídeos y site-config). Guarda el JSON ya serializado por clave, con TTL y un tope de
+// bytes; al pasarse se descartan primero las entradas más viejas.
+//
+// - Invalidación: los handlers que tocan noticias, tags, perfiles de autor (van en los bylines) o
+//   la configuración llaman a `invalidate` tras el commit. Las visitas no invalidan (cambian a cada momento): el orden de
+//   "más leídas" y los contadores se refrescan con el TTL. Con varias instancias, cada una tiene su
+//   caché y las demás se enteran al caducar.
+// - Coalescencia: con la entrada caducada y cientos de peticiones a la vez, solo una consulta a
+//   Postgres; las demás esperan su turno en el candado de la clave y encuentran el resultado.
+
+// X-Cache: HIT | MISS (BYPASS con la caché desactivada), para depurar desde el navegador
+pub const CACHE_HEADER: &str = "x-cache";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKey {
+    MostRead,
+    Featured,
+    Breaking,
+    Videos,
+    SiteConfig,
+}
+
+impl CacheKey {
+    const ALL: [CacheKey; 5] =
+        [CacheKey::MostRead, CacheKey::Featured, CacheKey::Breaking, CacheKey::Videos, CacheKey::SiteConfig];
+
+    // Todo lo que cambia al crear, editar, borrar o etiquetar una noticia (o al editar un autor)
+    pub const ARTICLE_LISTS: [CacheKey; 4] = [CacheKey::MostRead, CacheKey::Featured, CacheKey::Breaking, CacheKey::Videos];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CacheKey::MostRead => "most_read",
+            CacheKey::Featured => "featured",
+            CacheKey::Breaking => "breaking",
+            CacheKey::Videos => "videos",
+            CacheKey::SiteConfig => "site_config",
+        }
+    }
+}
+
+struct Entry {
+    body: Bytes,
+    stored_at: Instant,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<CacheKey, Entry>,
+    bytes: usize,
+    // Sube con cada invalidación: una carga que empezó antes no guarda datos viejos
+    generations: HashMap<CacheKey, u64>,
+}
+
+impl Entries {
+    fn remove(&mut self, key: CacheKey) {
+        if let Some(entry) = self.by_key.remove(&key) {
+            self.bytes -= entry.body.len();
+        }
+    }
+}
+
+// Vive en AppState (ver state.rs); los clones comparten las entradas
+#[derive(Clone)]
+pub struct ResponseCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    enabled: bool,
+    ttl: Duration,
+    max_bytes: usize,
+    entries: Mutex<Entries>,
+    // Un candado por clave: solo una carga a la vez
+    fills: HashMap<CacheKey, tokio::sync::Mutex<()>>,
+}
+
+impl ResponseCache {
+    pub fn new(config: &CacheConfig) -> ResponseCache {
+        ResponseCache {
+            inner: Arc::new(Inner {
+                enabled: config.enabled,
+                ttl: Duration::from_secs(config.ttl_secs),
+                max_bytes: config.max_bytes,
+                entries: Mutex::new(Entries::default()),
+                fills: CacheKey::ALL.into_iter().map(|key| (key, tokio::sync::Mutex::new(()))).collect(),
+            }),
+        }
+    }
+
+    // La respuesta de `key` si está fresca; si no, la calcula con `load`, la guarda y la devuelve.
+    // Los errores no se guardan: la siguiente petición vuelve a intentarlo
+    pub async fn get_or_load<T, F, Fut>(&self, key: CacheKey, load: F) -> Result<CachedJson, ApiError>
+    where
+        T: Serialize,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        if !self.inner.enabled {
+            return Ok(CachedJson { body: serialize(key, &load().await?)?, status: "BYPASS" });
+        }
+        if let Some(body) = self.lookup(key) {
+            return Ok(self.hit(key, body));
+        }
+
+        let _fill = self.inner.fills[&key].lock().await;
+        // Mientras esperábamos, otra petición pudo haberla cargado
+        if let Some(body) = self.lookup(key) {
+            return Ok(self.hit(key, body));
+        }
+        let generation = self.entries().generations.get(&key).copied().unwrap_or(0);
+        let body = serialize(key, &load().await?)?;
+        self.store(key, generation, body.clone());
+        metrics::record_cache(key.as_str(), "miss");
+        Ok(CachedJson { body, status: "MISS" })
+    }
+
+    pub fn invalidate(&self, keys: &[CacheKey]) {
+        if !self.inner.enabled {
+            return;
+        }
+        let mut entries = self.entries();
+        for key in keys {
+            entries.remove(*key);
+            *entries.generations.entry(*key).or_default() += 1;
+        }
+        tracing::debug!("cache_invalidated keys={:?}", keys.iter().map(|k| k.as_str()).collect::<Vec<_>>());
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.inner.entries.lock().expect("caché envenenada")
+    }
+
+    fn lookup(&self, key: CacheKey) -> Option<Bytes> {
+        let mut entries = self.entries();
+        let entry = entries.by_key.get(&key)?;
+        if entry.stored_at.elapsed() < self.inner.ttl {
+            return Some(entry.body.clone());
+        }
+        entries.remove(key);
+        None
+    }
+
+    fn hit(&self, key: CacheKey, body: Bytes) -> CachedJson {
+        metrics::record_cache(key.as_str(), "hit");
+        CachedJson { body, status: "HIT" }
+    }
+
+    fn store(&self, key: CacheKey, generation: u64, body: Bytes) {
+        // Una respuesta que no cabe ni sola se sirve pero no se guarda
+        if body.len() > self.inner.max_bytes {
+            tracing::warn!("cache_skip key={} bytes={} max_bytes={}", key.as_str(), body.len(), self.inner.max_bytes);
+            return;
+        }
+        let mut entries = self.entries();
+        if entries.generations.get(&key).copied().unwrap_or(0) != generation {
+            return;
+        }
+        entries.remove(key);
+        while entries.bytes + body.len() > self.inner.max_bytes {
+            let oldest = entries.by_key.iter().min_by_key(|(_, e)| e.stored_at).map(|(k, _)| *k);
+            let Some(oldest) = oldest else { break };
+            entries.remove(oldest);
+        }
+        entries.bytes += body.len();
+        entries.by_key.insert(key, Entry { body, stored_at: Instant::now() });
+    }
+}
+
+fn serialize<T: Serialize>(key: CacheKey, value: &T) -> Result<Bytes, ApiError> {
+    serde_json::to_vec(value).map(Bytes::from).map_err(|e| {
+        tracing::error!("Error serializando la respuesta {}: {}", key.as_str(), e);
+        ApiError::internal()
+    })
+}
+
+// Respuesta 200 con el JSON guardado
+pub struct CachedJson {
+    body: Bytes,
+    status: &'static str,
+}
+
+impl IntoResponse for CachedJson {
+    fn into_response(self) -> Response {
+        (
+            [
+                (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+                (HeaderName::from_static(CACHE_HEADER), HeaderValue::from_static(self.status)),
+            ],
+            self.body,
+        )
+            .into_response()
+    }
+}
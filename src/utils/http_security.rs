@@ -0,0 +1,75 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+use std::{sync::Arc, time::Duration};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use crate::{
+    config::{AppConfig, CorsConfig, Environment},
+    utils::{bootstrap::SETUP_TOKEN_HEADER, jwt::API_KEY_HEADER, rate_limit, request_id::REQUEST_ID_HEADER},
+};
+
+// CORS según [cors]: solo los orígenes de la lista (o cualquiera con "*"). Métodos y cabeceras
+// van explícitos porque con credenciales el navegador no acepta comodines.
+pub fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    let origins = if config.allows_any_origin() {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config.allowed_origins.iter().map(|o| HeaderValue::from_str(o).expect("Origen CORS validado en config")),
+        )
+    };
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_credentials(config.allow_credentials)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static(API_KEY_HEADER),
+            HeaderName::from_static(SETUP_TOKEN_HEADER),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static("traceparent"),
+            HeaderName::from_static("tracestate"),
+        ])
+        .expose_headers([
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            header::RETRY_AFTER,
+            HeaderName::from_static(rate_limit::LIMIT_HEADER),
+            HeaderName::from_static(rate_limit::REMAINING_HEADER),
+            HeaderName::from_static(rate_limit::RESET_HEADER),
+        ])
+        .max_age(Duration::from_secs(600))
+}
+
+// Las respuestas de la API son JSON: nada que cargar ni que enmarcar
+const API_CSP: &str = "default-src 'none'; frame-ancestors 'none'";
+// Imágenes subidas: se pueden ver directamente, pero si algo se cuela como imagen (un SVG o HTML
+// con extensión .png) no ejecuta scripts ni comparte origen con la API
+const UPLOADS_CSP: &str = "default-src 'none'; img-src 'self'; style-src 'unsafe-inline'; sandbox; frame-ancestors 'none'";
+
+// Cabeceras de seguridad en todas las respuestas (errores y preflights incluidos), salvo que el
+// handler ya haya puesto la suya. HSTS solo en production: en local se sirve por http.
+pub async fn security_headers(State(config): State<Arc<AppConfig>>, request: Request, next: Next) -> Response {
+    let is_upload = request.uri().path().starts_with("/uploads/");
+    let mut response = next.run(request).await;
+
+    let headers = response.headers_mut();
+    let csp = if is_upload { UPLOADS_CSP } else { API_CSP };
+    for (name, value) in [
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        (header::REFERRER_POLICY, "no-referrer"),
+        (header::X_FRAME_OPTIONS, "DENY"),
+        (header::CONTENT_SECURITY_POLICY, csp),
+    ] {
+        headers.entry(name).or_insert(HeaderValue::from_static(value));
+    }
+    if config.server.environment == Environment::Production {
+        headers
+            .entry(header::STRICT_TRANSPORT_SECURITY)
+            .or_insert(HeaderValue::from_static("max-age=31536000; includeSubDomains"));
+    }
+    response
+}
@@ -0,0 +1,63 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    response::Response,
+};
+use std::time::Duration;
+use tracing::{field::Empty, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use crate::{
+    models::user::Claims,
+    utils::{request_id::REQUEST_ID_HEADER, telemetry},
+};
+
+// Span por petición para el TraceLayer de routes: todo lo que se loguee dentro (handlers,
+// ApiError, auditoría...) lleva method, route (la plantilla, p. ej. /api/articles/:slug),
+// request_id y, tras autenticar, user_id. Al terminar se completan status y latency_ms.
+// Con OpenTelemetry activo (utils::telemetry) es además el span de servidor de la traza: continúa
+// la del `traceparent` entrante y se exporta como "GET /api/articles/:slug".
+pub fn make_span(request: &Request) -> Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or("-");
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-");
+
+    let span = tracing::info_span!(
+        "http_request",
+        method = %request.method(),
+        route,
+        request_id,
+        user_id = Empty,
+        status = Empty,
+        latency_ms = Empty,
+        otel.name = Empty,
+        otel.kind = Empty,
+        otel.status_code = Empty,
+    );
+    if telemetry::is_enabled() {
+        span.record("otel.name", format!("{} {}", request.method(), route));
+        span.record("otel.kind", "server");
+        // Solo falla si el span no llega a la capa de OpenTelemetry (filtrado)
+        let _ = span.set_parent(telemetry::extract_context(request.headers()));
+    }
+    span
+}
+
+pub fn on_response(response: &Response, latency: Duration, span: &Span) {
+    span.record("status", response.status().as_u16());
+    span.record("latency_ms", latency.as_millis() as u64);
+    if response.status().is_server_error() && telemetry::is_enabled() {
+        span.record("otel.status_code", "error");
+    }
+    tracing::info!("request_completed");
+}
+
+// Lo llaman los middlewares de auth en cuanto saben quién es
+pub fn record_user(claims: &Claims) {
+    Span::current().record("user_id", claims.user_id);
+}
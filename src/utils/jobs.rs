@@ -0,0 +1,67 @@
+use std::{fmt::Debug, future::Future};
+use tokio::time::Instant;
+use tokio_util::{
+    sync::{CancellationToken, WaitForCancellationFutureOwned},
+    task::TaskTracker,
+};
+use crate::utils::metrics;
+
+// Tareas de fondo lanzadas desde los handlers (limpiezas, trabajo que no debe retrasar la respuesta).
+// Vive en AppState: todas quedan registradas en el mismo tracker, así el proceso sabe cuáles siguen
+// en curso, y los errores se registran con el nombre de la tarea en lugar de perderse.
+//
+// También coordina el apagado (ver serve en main.rs): SIGTERM llama a stop(), los listeners HTTP
+// dejan de aceptar conexiones y, cuando terminan las peticiones en curso, drain() espera a las
+// tareas pendientes. Un worker de larga duración (planificador, volcado periódico...) debe salir
+// de su bucle en cuanto `stopping()` se resuelva, p. ej. con tokio::select!.
+#[derive(Clone, Default)]
+pub struct Jobs {
+    tracker: TaskTracker,
+    shutdown: CancellationToken,
+}
+
+impl Jobs {
+    pub fn new() -> Jobs {
+        Jobs::default()
+    }
+
+    pub fn spawn<F, E>(&self, name: &'static str, task: F)
+    where
+        F: Future<Output = Result<(), E>> + Send + 'static,
+        E: Debug + Send + 'static,
+    {
+        self.tracker.spawn(async move {
+            match task.await {
+                Ok(()) => metrics::record_job(name, "ok"),
+                Err(e) => {
+                    metrics::record_job(name, "error");
+                    tracing::error!("job_failed name={} error={:?}", name, e);
+                }
+            }
+        });
+    }
+
+    // Empieza el apagado
+    pub fn stop(&self) {
+        self.shutdown.cancel();
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    // Se resuelve al empezar el apagado
+    pub fn stopping(&self) -> WaitForCancellationFutureOwned {
+        self.shutdown.clone().cancelled_owned()
+    }
+
+    // Espera a las tareas en curso hasta `deadline`. Devuelve cuántas quedaron sin terminar
+    // (se cortan al salir del proceso)
+    pub async fn drain(&self, deadline: Instant) -> usize {
+        self.tracker.close();
+        match tokio::time::timeout_at(deadline, self.tracker.wait()).await {
+            Ok(()) => 0,
+            Err(_) => self.tracker.len(),
+        }
+    }
+}
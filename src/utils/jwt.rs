@@ -1,76 +1,169 @@
 use axum::{
-    http::{Request, StatusCode},
+    extract::State,
+    http::Request,
     middleware::Next,
     response::Response,
 };
 use axum_extra::headers::{Authorization, authorization::Bearer};
 use axum_extra::TypedHeader;
-use jsonwebtoken::{decode, DecodingKey, Validation};
-use crate::{models::user::Claims};
+use axum::http::StatusCode;
+use jsonwebtoken::{decode, decode_header, encode, Header, Validation};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use crate::{
+    db::DbPool,
+    error::ApiError,
+    models::{role::Role, two_factor::ChallengeClaims, user::Claims},
+    utils::{api_keys, http_trace, jwt_keys::JwtKeys},
+};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// Los tokens del segundo factor llevan otra audiencia: no sirven como token de sesión
+// aquí ni en los servicios que verifican nuestros JWT con el JWKS
+fn challenge_audience(keys: &JwtKeys) -> String {
+    format!("{}/2fa", keys.audience)
+}
+
+// Firma con la clave activa (kid en el header) y añade iss/aud
+fn sign<T: Serialize>(keys: &JwtKeys, claims: &T, audience: &str) -> Result<String, ApiError> {
+    let (kid, alg, key) = keys.signing();
+
+    let mut payload = serde_json::to_value(claims).map_err(|_| ApiError::internal())?;
+    payload["iss"] = keys.issuer.clone().into();
+    payload["aud"] = audience.into();
+
+    let mut header = Header::new(alg);
+    header.kid = Some(kid.to_string());
+
+    encode(&header, &payload, key).map_err(|e| {
+        tracing::error!("Error firmando token: {:?}", e);
+        ApiError::internal()
+    })
+}
+
+// Decodifica el token con la clave de su kid y verifica firma, expiración, iss y aud
+fn verify<T: DeserializeOwned>(keys: &JwtKeys, token: &str, audience: &str) -> Result<T, ApiError> {
+    let kid = decode_header(token)
+        .ok()
+        .and_then(|header| header.kid)
+        .ok_or_else(invalid_token)?;
+    // kid desconocido: clave retirada o token ajeno
+    let (alg, key) = keys.verifying(&kid).ok_or_else(invalid_token)?;
+
+    // Solo el algoritmo de esa clave (nada de "alg confusion")
+    let mut validation = Validation::new(*alg);
+    validation.set_issuer(&[&keys.issuer]);
+    validation.set_audience(&[audience]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+
+    decode::<T>(token, key, &validation)
+        .map(|data| data.claims)
+        // Token falso, expirado o manipulado
+        .map_err(|_| invalid_token())
+}
+
+fn invalid_token() -> ApiError {
+    ApiError::new(StatusCode::UNAUTHORIZED, "invalid_token", "Token inválido o expirado")
+}
+
+// Token de sesión (el que viaja en "Authorization: Bearer")
+pub fn encode_token(keys: &JwtKeys, claims: &Claims) -> Result<String, ApiError> {
+    sign(keys, claims, &keys.audience)
+}
+
+pub fn decode_claims(keys: &JwtKeys, token: &str) -> Result<Claims, ApiError> {
+    verify(keys, token, &keys.audience)
+}
 
-// Esta función se ejecutará ANTES de llegar al handler de crear noticia
+// Token temporal del segundo factor (login pendiente de TOTP)
+pub fn encode_challenge(keys: &JwtKeys, claims: &ChallengeClaims) -> Result<String, ApiError> {
+    sign(keys, claims, &challenge_audience(keys))
+}
+
+pub fn decode_challenge(keys: &JwtKeys, token: &str) -> Result<ChallengeClaims, ApiError> {
+    verify(keys, token, &challenge_audience(keys))
+}
+
+// Verifica que la sesión del token siga viva (no revocada ni expirada), registra su último uso
+// y devuelve el rol actual del usuario: un cambio de rol aplica sin esperar a un nuevo login
+pub async fn touch_session(pool: &DbPool, claims: &Claims) -> Result<Role, ApiError> {
+    let result = sqlx::query_scalar!(
+        r#"
+        UPDATE user_sessions s
+        SET last_used_at = NOW()
+        FROM users u
+        WHERE s.id = $1 AND s.user_id = $2 AND u.id = s.user_id
+          AND s.revoked_at IS NULL AND s.expires_at > NOW()
+        RETURNING u.role
+        "#,
+        claims.sid,
+        claims.user_id
+    )
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(Some(role)) => role.parse::<Role>().map_err(|e| {
+            tracing::error!("Usuario {} con rol inválido: {}", claims.user_id, e);
+            ApiError::forbidden("Rol de usuario inválido")
+        }),
+        // Sesión cerrada remotamente
+        Ok(None) => Err(ApiError::new(StatusCode::UNAUTHORIZED, "session_revoked", "La sesión ya no es válida")),
+        Err(e) => Err(ApiError::db("Error verificando sesión")(e)),
+    }
+}
+
+// Token de sesión completo: firma + sesión viva + rol actual
+pub async fn session_claims(pool: &DbPool, keys: &JwtKeys, token: &str) -> Result<Claims, ApiError> {
+    let mut claims = decode_claims(keys, token)?;
+    claims.role = touch_session(pool, &claims).await?;
+    Ok(claims)
+}
+
+// Esta función se ejecutará ANTES de llegar al handler de crear noticia.
+// Acepta el JWT de sesión o una API key en "X-API-Key" (integraciones).
+// Los permisos de cada ruta se comprueban después con utils::permissions::require_permission.
 pub async fn auth_middleware(
+    State(pool): State<DbPool>,
+    State(keys): State<Arc<JwtKeys>>,
     // Axum extrae automáticamente el header "Authorization: Bearer <token>"
-    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
     mut request: Request<axum::body::Body>,
     next: Next,
-) -> Result<Response, StatusCode> {
-    // 1. Obtener el token del header
-    let token = auth.token();
-
-    // 2. Obtener el secreto
-    let secret = std::env::var("JWT_SECRET").map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    // 3. Decodificar y verificar firma
-    let validation = Validation::default();
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &validation,
-    );
-
-    match token_data {
-        Ok(data) => {
-            // Adjuntamos claims para que los handlers sepan quién es el usuario
-            request.extensions_mut().insert(data.claims);
-            Ok(next.run(request).await)
-        }
-        Err(_) => {
-            // Token falso, expirado o manipulado
-            Err(StatusCode::UNAUTHORIZED)
+) -> Result<Response, ApiError> {
+    let api_key = request.headers().get(API_KEY_HEADER).cloned();
+
+    let claims = match (api_key, bearer) {
+        (Some(key), _) => {
+            let key = key.to_str().map_err(|_| ApiError::unauthorized("API key inválida"))?;
+            api_keys::authenticate(&pool, key).await?
         }
-    }
+        (None, Some(TypedHeader(auth))) => session_claims(&pool, &keys, auth.token()).await?,
+        (None, None) => return Err(missing_credentials()),
+    };
+
+    // Adjuntamos claims para que los handlers sepan quién es el usuario
+    http_trace::record_user(&claims);
+    request.extensions_mut().insert(claims);
+    Ok(next.run(request).await)
 }
 
-pub async fn admin_middleware(
-    TypedHeader(auth): TypedHeader<Authorization<Bearer>>,
+// Solo JWT de sesión: rutas /api/me/* (una API key no tiene sesiones ni 2FA propios)
+pub async fn session_middleware(
+    State(pool): State<DbPool>,
+    State(keys): State<Arc<JwtKeys>>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
     mut request: Request<axum::body::Body>,
     next: Next,
-) -> Result<Response, StatusCode> {
-    // 1. Obtener y decodificar token (igual que el otro middleware)
-    let token = auth.token();
-    let secret = std::env::var("JWT_SECRET").map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    let validation = Validation::default();
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &validation,
-    );
-
-    match token_data {
-        Ok(data) => {
-            // 2. VERIFICACIÓN EXTRA: ¿Es Admin?
-            if data.claims.role == "admin" {
-                // Adjuntamos claims por si se necesitan aguas abajo
-                request.extensions_mut().insert(data.claims);
-                // Si es admin, pase señor
-                Ok(next.run(request).await)
-            } else {
-                // Si es editor, error 403 (Prohibido)
-                Err(StatusCode::FORBIDDEN)
-            }
-        }
-        Err(_) => Err(StatusCode::UNAUTHORIZED),
-    }
+) -> Result<Response, ApiError> {
+    let TypedHeader(auth) = bearer.ok_or_else(missing_credentials)?;
+    let claims = session_claims(&pool, &keys, auth.token()).await?;
+    http_trace::record_user(&claims);
+    request.extensions_mut().insert(claims);
+    Ok(next.run(request).await)
+}
+
+fn missing_credentials() -> ApiError {
+    ApiError::unauthorized("Falta el header Authorization: Bearer <token>")
 }
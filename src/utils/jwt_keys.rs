@@ -0,0 +1,169 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey,
+};
+use simple_asn1::{oid, ASN1Block};
+use std::collections::HashMap;
+use crate::config::JwtConfig;
+
+// Llavero JWT: lo carga serve al arrancar (ver load) y vive en AppState (ver state.rs).
+//
+// En JWT_KEYS_DIR ([jwt] keys_dir, por defecto "keys") cada clave es un par de ficheros:
+//   <kid>.pub.pem  clave pública (SPKI) -> verifica tokens y se publica en el JWKS
+//   <kid>.pem      clave privada (PKCS#8) -> solo hace falta la de JWT_ACTIVE_KID, que firma
+// Rotar = añadir un par nuevo, apuntar JWT_ACTIVE_KID a él y borrar el .pub.pem viejo
+// cuando hayan caducado sus tokens (24h).
+pub struct JwtKeys {
+    pub issuer: String,
+    pub audience: String,
+    pub jwks: JwkSet,
+    signing_kid: String,
+    signing_alg: Algorithm,
+    signing_key: EncodingKey,
+    verifying: HashMap<String, (Algorithm, DecodingKey)>,
+}
+
+impl JwtKeys {
+    pub fn signing(&self) -> (&str, Algorithm, &EncodingKey) {
+        (&self.signing_kid, self.signing_alg, &self.signing_key)
+    }
+
+    pub fn verifying(&self, kid: &str) -> Option<&(Algorithm, DecodingKey)> {
+        self.verifying.get(kid)
+    }
+}
+
+pub fn load(config: &JwtConfig) -> Result<JwtKeys, String> {
+    let dir = config.keys_dir.as_path();
+    let active_kid = config.active_kid.as_str();
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("No se pudo leer JWT_KEYS_DIR {}: {}", dir.display(), e))?;
+
+    let mut jwks = Vec::new();
+    let mut verifying = HashMap::new();
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(kid) = file_name.strip_suffix(".pub.pem") else { continue };
+
+        let pem_bytes = std::fs::read(entry.path())
+            .map_err(|e| format!("No se pudo leer {}: {}", file_name, e))?;
+        let jwk = public_jwk(kid, &pem_bytes).map_err(|e| format!("{}: {}", file_name, e))?;
+        let alg = algorithm_of(&jwk);
+        let key = DecodingKey::from_jwk(&jwk).map_err(|e| format!("{}: {}", file_name, e))?;
+
+        verifying.insert(kid.to_string(), (alg, key));
+        jwks.push(jwk);
+    }
+
+    let signing_alg = verifying
+        .get(active_kid)
+        .map(|(alg, _)| *alg)
+        .ok_or_else(|| format!("No existe {}/{}.pub.pem para JWT_ACTIVE_KID", dir.display(), active_kid))?;
+
+    let private_path = dir.join(format!("{}.pem", active_kid));
+    let private_pem = std::fs::read(&private_path)
+        .map_err(|e| format!("No se pudo leer {}: {}", private_path.display(), e))?;
+    let signing_key = match signing_alg {
+        Algorithm::EdDSA => EncodingKey::from_ed_pem(&private_pem),
+        _ => EncodingKey::from_rsa_pem(&private_pem),
+    }
+    .map_err(|e| format!("{}: {}", private_path.display(), e))?;
+
+    // Orden estable en el JWKS (la activa primero)
+    jwks.sort_by_key(|jwk| jwk.common.key_id.as_deref() != Some(active_kid));
+
+    tracing::info!(
+        "🔑 Claves JWT cargadas: firma kid={} ({:?}), verificación kids={:?}",
+        active_kid,
+        signing_alg,
+        verifying.keys().collect::<Vec<_>>()
+    );
+
+    Ok(JwtKeys {
+        issuer: config.issuer.clone(),
+        audience: config.audience.clone(),
+        jwks: JwkSet { keys: jwks },
+        signing_kid: active_kid.to_string(),
+        signing_alg,
+        signing_key,
+        verifying,
+    })
+}
+
+fn algorithm_of(jwk: &Jwk) -> Algorithm {
+    match jwk.algorithm {
+        AlgorithmParameters::OctetKeyPair(_) => Algorithm::EdDSA,
+        _ => Algorithm::RS256,
+    }
+}
+
+fn b64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// Convierte una clave pública PEM (SPKI Ed25519 o RSA) en su JWK
+fn public_jwk(kid: &str, pem_bytes: &[u8]) -> Result<Jwk, String> {
+    let pem = pem::parse(pem_bytes).map_err(|e| format!("PEM inválido: {}", e))?;
+    if pem.tag() != "PUBLIC KEY" {
+        return Err(format!("se esperaba 'PUBLIC KEY' y llegó '{}'", pem.tag()));
+    }
+
+    let blocks = simple_asn1::from_der(pem.contents()).map_err(|e| format!("DER inválido: {}", e))?;
+    let (algorithm_oid, key_bytes) = match blocks.as_slice() {
+        [ASN1Block::Sequence(_, spki)] => match spki.as_slice() {
+            [ASN1Block::Sequence(_, alg), ASN1Block::BitString(_, _, bytes)] => match alg.first() {
+                Some(ASN1Block::ObjectIdentifier(_, oid)) => (oid.clone(), bytes.clone()),
+                _ => return Err("SPKI sin OID de algoritmo".to_string()),
+            },
+            _ => return Err("SPKI mal formado".to_string()),
+        },
+        _ => return Err("SPKI mal formado".to_string()),
+    };
+
+    let (key_algorithm, algorithm) = if algorithm_oid == oid!(1, 3, 101, 112) {
+        // Ed25519: la BIT STRING es directamente la clave de 32 bytes
+        (
+            KeyAlgorithm::EdDSA,
+            AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: b64(&key_bytes),
+            }),
+        )
+    } else if algorithm_oid == oid!(1, 2, 840, 113549, 1, 1, 1) {
+        // RSA: la BIT STRING contiene SEQUENCE { n INTEGER, e INTEGER }
+        let rsa = simple_asn1::from_der(&key_bytes).map_err(|e| format!("RSA inválida: {}", e))?;
+        let (n, e) = match rsa.as_slice() {
+            [ASN1Block::Sequence(_, parts)] => match parts.as_slice() {
+                [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => (n.to_bytes_be().1, e.to_bytes_be().1),
+                _ => return Err("RSA mal formada".to_string()),
+            },
+            _ => return Err("RSA mal formada".to_string()),
+        };
+        (
+            KeyAlgorithm::RS256,
+            AlgorithmParameters::RSA(RSAKeyParameters {
+                key_type: RSAKeyType::RSA,
+                n: b64(&n),
+                e: b64(&e),
+            }),
+        )
+    } else {
+        return Err("solo se soportan claves Ed25519 (EdDSA) y RSA (RS256)".to_string());
+    };
+
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm,
+    })
+}
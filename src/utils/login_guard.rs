@@ -0,0 +1,119 @@
+use chrono::{Duration, Utc};
+use crate::db::DbPool;
+
+// Configuración del bloqueo: pasada la ventana sin fallos, el contador vuelve a empezar
+const FAILURE_WINDOW_MINUTES: i32 = 15;
+const LOCKOUT_MINUTES: i64 = 15;
+
+// Política por tipo de clave: a partir de `backoff_after` fallos hay espera exponencial
+// (1s, 2s, 4s...) y al llegar a `max_failures` la clave queda bloqueada LOCKOUT_MINUTES
+struct ThrottlePolicy {
+    scope: &'static str,
+    backoff_after: i32,
+    max_failures: i32,
+}
+
+const ACCOUNT_POLICY: ThrottlePolicy = ThrottlePolicy { scope: "account", backoff_after: 3, max_failures: 5 };
+// Una IP puede ser una redacción entera detrás de NAT: umbral más alto
+const IP_POLICY: ThrottlePolicy = ThrottlePolicy { scope: "ip", backoff_after: 10, max_failures: 20 };
+
+fn account_key(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+fn lock_duration(policy: &ThrottlePolicy, failures: i32) -> Option<Duration> {
+    let lockout = Duration::minutes(LOCKOUT_MINUTES);
+    if failures >= policy.max_failures {
+        return Some(lockout);
+    }
+    if failures >= policy.backoff_after {
+        let backoff = Duration::seconds(1i64 << (failures - policy.backoff_after).min(20));
+        return Some(backoff.min(lockout));
+    }
+    None
+}
+
+// ¿La cuenta o la IP están en espera/bloqueadas? (no se verifica la contraseña en ese caso)
+pub async fn is_locked(pool: &DbPool, email: &str, ip: &str) -> Result<bool, sqlx::Error> {
+    let locked = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM login_throttle
+            WHERE ((scope = 'account' AND key = $1) OR (scope = 'ip' AND key = $2))
+              AND locked_until > NOW()
+        ) as "locked!: bool"
+        "#,
+        account_key(email),
+        ip
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(locked)
+}
+
+// Registra un intento fallido para la cuenta y para la IP
+// Devuelve true si este fallo acaba de bloquear la cuenta (para la auditoría)
+pub async fn register_failure(pool: &DbPool, email: &str, ip: &str) -> Result<bool, sqlx::Error> {
+    let account_locked = register_failure_for(pool, &ACCOUNT_POLICY, &account_key(email)).await?;
+    register_failure_for(pool, &IP_POLICY, ip).await?;
+    Ok(account_locked)
+}
+
+async fn register_failure_for(pool: &DbPool, policy: &ThrottlePolicy, key: &str) -> Result<bool, sqlx::Error> {
+    let failures = sqlx::query_scalar!(
+        r#"
+        INSERT INTO login_throttle (scope, key, failures, last_failure_at)
+        VALUES ($1, $2, 1, NOW())
+        ON CONFLICT (scope, key) DO UPDATE SET
+            failures = CASE
+                WHEN login_throttle.last_failure_at < NOW() - make_interval(mins => $3) THEN 1
+                ELSE login_throttle.failures + 1
+            END,
+            last_failure_at = NOW()
+        RETURNING failures
+        "#,
+        policy.scope,
+        key,
+        FAILURE_WINDOW_MINUTES
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if let Some(duration) = lock_duration(policy, failures) {
+        let locked_until = Utc::now() + duration;
+        sqlx::query!(
+            "UPDATE login_throttle SET locked_until = $3 WHERE scope = $1 AND key = $2",
+            policy.scope,
+            key,
+            locked_until
+        )
+        .execute(pool)
+        .await?;
+
+        if failures >= policy.max_failures {
+            tracing::warn!(
+                "login_lockout scope={} key={} failures={} until={}",
+                policy.scope,
+                key,
+                failures,
+                locked_until
+            );
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+// Login correcto: olvidamos los fallos de la cuenta (los de la IP caducan solos)
+pub async fn clear_account(pool: &DbPool, email: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM login_throttle WHERE scope = 'account' AND key = $1",
+        account_key(email)
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
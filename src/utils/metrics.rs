@@ -0,0 +1,103 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+use std::time::Instant;
+use crate::db::DbPool;
+
+// Métricas en formato Prometheus (GET /metrics, ver handlers::metrics). Se registran con las
+// macros de la crate `metrics` (counter!, histogram!, gauge!) desde donde ocurre cada cosa; el
+// recorder global las acumula y `render` las vuelca en texto. Nombres y etiquetas en el README.
+//
+// Las etiquetas son siempre de un conjunto cerrado (plantilla de ruta, método, resultado...):
+// nunca ids, slugs ni emails, que dispararían el número de series.
+
+// Segundos; de 5ms a 10s cubre desde un GET cacheado hasta un upload lento
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+// Vacío (sin recorder) salvo en `serve` con [metrics] enabled: así los subcomandos y las pruebas
+// no instalan nada global y /metrics responde 404
+#[derive(Clone, Default)]
+pub struct Metrics {
+    handle: Option<PrometheusHandle>,
+}
+
+impl Metrics {
+    // Instala el recorder global: una sola vez por proceso
+    pub fn install() -> Result<Metrics, BuildError> {
+        let handle = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Full("http_request_duration_seconds".into()), LATENCY_BUCKETS)?
+            .install_recorder()?;
+        Ok(Metrics { handle: Some(handle) })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    // Los gauges del pool se leen en el momento del scrape, no hace falta una tarea periódica
+    pub fn render(&self, pool: &DbPool) -> Option<String> {
+        let handle = self.handle.as_ref()?;
+        let size = pool.size();
+        let idle = pool.num_idle() as u32;
+        metrics::gauge!("db_pool_connections", "state" => "idle").set(idle);
+        metrics::gauge!("db_pool_connections", "state" => "active").set(size.saturating_sub(idle));
+        metrics::gauge!("db_pool_max_connections").set(pool.options().get_max_connections());
+        Some(handle.render())
+    }
+}
+
+// Middleware de routes: peticiones y latencia por ruta. Va en Router::layer, que corre después
+// del enrutado, así que MatchedPath ya trae la plantilla (/api/articles/:slug); lo que no casa con
+// ninguna ruta se agrupa en "unmatched" para que un escaneo de URLs no cree series nuevas.
+// Los estáticos de /uploads (nest_service) no traen MatchedPath: van juntos en "/uploads/*".
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_string(),
+        None if request.uri().path().starts_with("/uploads/") => "/uploads/*".to_string(),
+        None => "unmatched".to_string(),
+    };
+    let method = request.method().to_string();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::counter!("http_requests_total", "method" => method.clone(), "route" => route.clone(), "status" => status)
+        .increment(1);
+    metrics::histogram!("http_request_duration_seconds", "method" => method, "route" => route)
+        .record(started.elapsed().as_secs_f64());
+    response
+}
+
+// method: password | 2fa | oidc; outcome: success | failure | locked | challenge (contraseña
+// correcta, falta el segundo factor)
+pub fn record_login(method: &'static str, outcome: &'static str) {
+    metrics::counter!("auth_logins_total", "method" => method, "outcome" => outcome).increment(1);
+}
+
+pub fn record_upload(bytes: usize) {
+    metrics::counter!("uploads_total").increment(1);
+    metrics::counter!("upload_bytes_total").increment(bytes as u64);
+}
+
+pub fn record_article_view() {
+    metrics::counter!("article_views_total").increment(1);
+}
+
+// group: public_read | search | views | auth | uploads (ver utils::rate_limit)
+pub fn record_rate_limited(group: &'static str) {
+    metrics::counter!("rate_limited_total", "group" => group).increment(1);
+}
+
+// key: most_read | featured | breaking | videos | site_config; outcome: hit | miss (ver utils::cache)
+pub fn record_cache(key: &'static str, outcome: &'static str) {
+    metrics::counter!("response_cache_requests_total", "key" => key, "outcome" => outcome).increment(1);
+}
+
+// outcome: ok | error (ver utils::jobs)
+pub fn record_job(name: &'static str, outcome: &'static str) {
+    metrics::counter!("background_jobs_total", "name" => name, "outcome" => outcome).increment(1);
+}
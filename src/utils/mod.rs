@@ -1,2 +1,23 @@
 pub mod security;
-pub mod jwt;
\ No newline at end of file
+pub mod jwt;
+pub mod jwt_keys;
+pub mod request_info;
+pub mod login_guard;
+pub mod totp;
+pub mod permissions;
+pub mod api_keys;
+pub mod oidc;
+pub mod slug;
+pub mod audit;
+pub mod request_id;
+pub mod validation;
+pub mod jobs;
+pub mod bootstrap;
+pub mod http_trace;
+pub mod metrics;
+pub mod telemetry;
+pub mod shutdown;
+pub mod http_security;
+pub mod rate_limit;
+pub mod cache;
+pub mod pagination;
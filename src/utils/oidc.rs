@@ -0,0 +1,240 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::OnceCell;
+use crate::models::role::Role;
+
+// SSO OpenID Connect para la redacción (authorization code + PKCE).
+//
+// Se activa definiendo OIDC_ISSUER_URL; la config la carga crate::config (también admite la
+// sección [oidc] del TOML y rechaza el arranque si está a medias). El resto de variables:
+//   OIDC_CLIENT_ID         (obligatoria)
+//   OIDC_CLIENT_SECRET     (opcional: clientes públicos usan solo PKCE)
+//   OIDC_REDIRECT_URL      URL pública de /api/auth/oidc/callback registrada en el IdP
+//   OIDC_GROUPS_CLAIM      claim con los grupos del usuario (por defecto "groups")
+//   OIDC_ROLE_MAP          "grupo=rol,grupo2=rol2"; gana el rol más alto, sin coincidencia no hay acceso
+//   OIDC_POST_LOGIN_REDIRECT  opcional: URL del panel a la que se devuelve el JWT en el fragmento
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub groups_claim: String,
+    pub role_map: Vec<(String, Role)>,
+    pub post_login_redirect: Option<String>,
+}
+
+// Lo que nos interesa del documento .well-known/openid-configuration
+#[derive(Debug, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+// Identidad ya validada que devuelve el IdP
+#[derive(Debug)]
+pub struct Identity {
+    pub subject: String,
+    pub email: String,
+    // Solo si el IdP lo afirma (email_verified = true); sin el claim no se enlaza por email
+    pub email_verified: bool,
+    pub username: Option<String>,
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    email_verified: Option<bool>,
+    preferred_username: Option<String>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+// Cliente del IdP: HTTP + caché del discovery. Vive en AppState (clonarlo es barato) para
+// poder sustituirlo por uno que apunte a un IdP falso
+#[derive(Clone)]
+pub struct OidcClient {
+    inner: Arc<ClientInner>,
+}
+
+impl Default for OidcClient {
+    fn default() -> Self {
+        OidcClient::new()
+    }
+}
+
+struct ClientInner {
+    http: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+}
+
+impl OidcClient {
+    pub fn new() -> OidcClient {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("No se pudo crear el cliente HTTP");
+        OidcClient { inner: Arc::new(ClientInner { http, metadata: OnceCell::new() }) }
+    }
+
+    // Discovery: se pide la primera vez que hace falta y se cachea (si falla, se reintenta en el siguiente login)
+    pub async fn metadata(&self, config: &OidcConfig) -> Result<&ProviderMetadata, String> {
+        self.inner
+            .metadata
+            .get_or_try_init(|| async {
+                let url = format!("{}/.well-known/openid-configuration", config.issuer_url);
+                let metadata: ProviderMetadata = self.inner.http
+                    .get(&url)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(|e| format!("discovery {}: {}", url, e))?
+                    .json()
+                    .await
+                    .map_err(|e| format!("discovery {}: {}", url, e))?;
+
+                if metadata.issuer.trim_end_matches('/') != config.issuer_url {
+                    return Err(format!("el issuer publicado ({}) no coincide con OIDC_ISSUER_URL", metadata.issuer));
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    // Canjea el código en el token endpoint y valida el id_token (firma, iss, aud, exp y nonce)
+    pub async fn exchange_code(
+        &self,
+        config: &OidcConfig,
+        metadata: &ProviderMetadata,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<Identity, String> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("client_id", config.client_id.as_str()),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &config.client_secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+
+        let tokens: TokenResponse = self.inner.http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("token endpoint: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("respuesta del token endpoint: {}", e))?;
+
+        let claims = self.validate_id_token(config, metadata, &tokens.id_token).await?;
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err("nonce del id_token no coincide".to_string());
+        }
+        if claims.email_verified == Some(false) {
+            return Err(format!("email sin verificar para sub={}", claims.sub));
+        }
+        let email = claims.email.ok_or_else(|| format!("id_token sin email para sub={}", claims.sub))?;
+
+        // Los grupos pueden venir como lista o como un único string
+        let groups = match claims.extra.get(&config.groups_claim) {
+            Some(serde_json::Value::Array(values)) => {
+                values.iter().filter_map(|v| v.as_str().map(str::to_string)).collect()
+            }
+            Some(serde_json::Value::String(group)) => vec![group.clone()],
+            _ => Vec::new(),
+        };
+
+        Ok(Identity {
+            subject: claims.sub,
+            email,
+            email_verified: claims.email_verified == Some(true),
+            username: claims.preferred_username,
+            groups,
+        })
+    }
+
+    async fn validate_id_token(
+        &self,
+        config: &OidcConfig,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+    ) -> Result<IdTokenClaims, String> {
+        let header = jsonwebtoken::decode_header(id_token).map_err(|e| format!("id_token: {}", e))?;
+
+        // Solo firmas asimétricas: un HS* se verificaría con el client_secret, que no es una clave del IdP
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(format!("algoritmo de id_token no permitido: {:?}", header.alg));
+        }
+
+        // JWKS pedido en cada login: son pocos y así seguimos las rotaciones del IdP sin caché que invalidar
+        let jwks: JwkSet = self.inner.http
+            .get(&metadata.jwks_uri)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("JWKS del IdP: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("JWKS del IdP: {}", e))?;
+
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| format!("kid {:?} no está en el JWKS del IdP", header.kid))?;
+        let key = DecodingKey::from_jwk(jwk).map_err(|e| format!("JWK del IdP: {}", e))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        jsonwebtoken::decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| format!("id_token inválido: {}", e))
+    }
+}
+
+pub fn random_token() -> String {
+    let mut buf = [0u8; 32];
+    OsRng.fill_bytes(&mut buf);
+    URL_SAFE_NO_PAD.encode(buf)
+}
+
+// PKCE S256: BASE64URL(SHA256(code_verifier))
+pub fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+// Rol según los grupos del IdP: el más alto de los que encajen en OIDC_ROLE_MAP
+pub fn role_for(config: &OidcConfig, groups: &[String]) -> Option<Role> {
+    [Role::Admin, Role::ChiefEditor, Role::Editor, Role::Contributor]
+        .into_iter()
+        .find(|role| {
+            config
+                .role_map
+                .iter()
+                .any(|(group, mapped)| mapped == role && groups.contains(group))
+        })
+}
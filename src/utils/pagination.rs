@@ -0,0 +1,21 @@
+use crate::error::ApiError;
+
+// ?page=&per_page= de los listados paginados. per_page se acota a [1, máximo del listado] y page
+// tiene techo: sin él, un page enorme desborda (page - 1) * per_page (pánico en debug, OFFSET
+// negativo y 500 en release). 10 000 páginas ya son más de lo que nadie navega
+pub const MAX_PAGE: i64 = 10_000;
+
+pub struct Page {
+    pub page: i64,
+    pub per_page: i64,
+    pub offset: i64,
+}
+
+pub fn page(page: Option<i64>, per_page: Option<i64>, default_per_page: i64, max_per_page: i64) -> Result<Page, ApiError> {
+    let page = page.unwrap_or(1).max(1);
+    if page > MAX_PAGE {
+        return Err(ApiError::bad_request(format!("page debe estar entre 1 y {}", MAX_PAGE)));
+    }
+    let per_page = per_page.unwrap_or(default_per_page).clamp(1, max_per_page);
+    Ok(Page { page, per_page, offset: (page - 1) * per_page })
+}
@@ -0,0 +1,34 @@
+use axum::{
+    extract::State,
+    http::Request,
+    middleware::Next,
+    response::Response,
+    Extension,
+};
+use crate::{
+    error::ApiError,
+    models::{role::Permission, user::Claims},
+};
+
+// Err(403 con el permiso que falta) si el rol del token no lo incluye (para chequeos dentro de handlers).
+// Con API key, además debe estar entre los permisos de la key.
+pub fn check(claims: &Claims, permission: Permission) -> Result<(), ApiError> {
+    let in_scope = claims.scopes.as_ref().is_none_or(|scopes| scopes.contains(&permission));
+    if claims.role.has(permission) && in_scope {
+        Ok(())
+    } else {
+        Err(ApiError::missing_permission(permission))
+    }
+}
+
+// Capa de ruta: `post(h).route_layer(from_fn_with_state(Permission::TagsManage, require_permission))`.
+// Va detrás de auth_middleware, que es quien deja los Claims en la request.
+pub async fn require_permission(
+    State(permission): State<Permission>,
+    Extension(claims): Extension<Claims>,
+    request: Request<axum::body::Body>,
+    next: Next,
+) -> Result<Response, ApiError> {
+    check(&claims, permission)?;
+    Ok(next.run(request).await)
+}
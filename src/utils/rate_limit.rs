@@ -0,0 +1,288 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use crate::{
+    config::RateLimitConfig,
+    error::ApiError,
+    models::user::Claims,
+    utils::{jobs::Jobs, metrics, request_info::TrustedProxies},
+};
+
+// Límite de peticiones por cliente con token bucket: cada cliente tiene un cubo de `requests`
+// fichas que se rellena de forma continua (requests por `period`); cada petición gasta una y sin
+// fichas se responde 429 con Retry-After. Permite ráfagas cortas sin dejar pasar un goteo sostenido.
+//
+// El límite es por grupo de rutas (ver routes) y por cliente: el usuario autenticado si la ruta
+// pasa por auth_middleware, si no la IP (ver request_info::TrustedProxies y [server] trusted_proxies).
+// Los contadores viven en memoria: con varias instancias, cada una lleva los suyos.
+
+pub const LIMIT_HEADER: &str = "x-ratelimit-limit";
+pub const REMAINING_HEADER: &str = "x-ratelimit-remaining";
+// Segundos hasta que el cubo vuelve a estar lleno
+pub const RESET_HEADER: &str = "x-ratelimit-reset";
+
+// Cada cuánto se tiran los cubos llenos (clientes que ya no gastan)
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateGroup {
+    // Lecturas públicas (noticias, categorías, etiquetas, autores, site-config)
+    PublicRead,
+    // GET con ?search=: van a Postgres con ILIKE, bastante más caras que una lectura normal
+    Search,
+    // POST /api/articles/:slug/view
+    Views,
+    // Login, registro, 2FA y SSO
+    Auth,
+    Uploads,
+}
+
+impl RateGroup {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RateGroup::PublicRead => "public_read",
+            RateGroup::Search => "search",
+            RateGroup::Views => "views",
+            RateGroup::Auth => "auth",
+            RateGroup::Uploads => "uploads",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl Rate {
+    // Sin límite
+    pub const OFF: Rate = Rate { requests: 0, period: Duration::from_secs(1) };
+
+    pub const fn per_minute(requests: u32) -> Rate {
+        Rate { requests, period: Duration::from_secs(60) }
+    }
+
+    pub fn is_off(&self) -> bool {
+        self.requests == 0
+    }
+
+    fn per_second(&self) -> f64 {
+        self.requests as f64 / self.period.as_secs_f64()
+    }
+}
+
+// "300/min", "10/s", "1000/h" u "off"
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "off" {
+            return Ok(Rate::OFF);
+        }
+        let (requests, unit) = s.split_once('/').ok_or_else(|| format!("'{}' no tiene la forma N/unidad", s))?;
+        let requests: u32 = requests.trim().parse().map_err(|_| format!("'{}' no es un número", requests))?;
+        let period = match unit.trim() {
+            "s" => Duration::from_secs(1),
+            "min" => Duration::from_secs(60),
+            "h" => Duration::from_secs(3600),
+            other => return Err(format!("unidad desconocida '{}' (s, min, h)", other)),
+        };
+        if requests == 0 {
+            return Err("0 peticiones: para desactivar el límite usa 'off'".into());
+        }
+        Ok(Rate { requests, period })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ClientKey {
+    User(i64),
+    Ip(IpAddr),
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+enum Decision {
+    Allowed { remaining: u32, reset: Duration },
+    Limited { retry_after: Duration, reset: Duration },
+}
+
+// Vive en AppState (ver state.rs); los clones comparten los cubos
+#[derive(Clone)]
+pub struct RateLimiter {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    enabled: bool,
+    rates: HashMap<RateGroup, Rate>,
+    // Para sacar la IP de los clientes anónimos
+    proxies: TrustedProxies,
+    buckets: Mutex<HashMap<(RateGroup, ClientKey), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig, proxies: TrustedProxies) -> RateLimiter {
+        let rates = HashMap::from([
+            (RateGroup::PublicRead, config.public_read),
+            (RateGroup::Search, config.search),
+            (RateGroup::Views, config.views),
+            (RateGroup::Auth, config.auth),
+            (RateGroup::Uploads, config.uploads),
+        ]);
+        RateLimiter {
+            inner: Arc::new(Inner { enabled: config.enabled, rates, proxies, buckets: Mutex::new(HashMap::new()) }),
+        }
+    }
+
+    // None si el grupo no tiene límite
+    fn rate(&self, group: RateGroup) -> Option<Rate> {
+        if !self.inner.enabled {
+            return None;
+        }
+        self.inner.rates.get(&group).copied().filter(|rate| !rate.is_off())
+    }
+
+    fn check(&self, group: RateGroup, rate: Rate, key: ClientKey) -> Decision {
+        let now = Instant::now();
+        let capacity = rate.requests as f64;
+        let per_second = rate.per_second();
+
+        let mut buckets = self.inner.buckets.lock().expect("rate limiter envenenado");
+        let bucket = buckets.entry((group, key)).or_insert(Bucket { tokens: capacity, updated: now });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        // Hasta tener el cubo lleno, no hasta la siguiente ficha (eso es Retry-After)
+        let reset = Duration::from_secs_f64((capacity - bucket.tokens) / per_second);
+        if allowed {
+            Decision::Allowed { remaining: bucket.tokens.floor() as u32, reset }
+        } else {
+            Decision::Limited { retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / per_second), reset }
+        }
+    }
+
+    // Un cubo que ya se habría rellenado entero es igual que uno nuevo: fuera
+    fn sweep(&self) {
+        let now = Instant::now();
+        let mut buckets = self.inner.buckets.lock().expect("rate limiter envenenado");
+        buckets.retain(|(group, _), bucket| {
+            let Some(rate) = self.inner.rates.get(group) else { return false };
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens + elapsed * rate.per_second() < rate.requests as f64
+        });
+    }
+
+    // Worker de limpieza; sale al empezar el apagado (ver utils::jobs)
+    pub fn start_sweeper(&self, jobs: &Jobs) {
+        if !self.inner.enabled {
+            return;
+        }
+        let limiter = self.clone();
+        let stopping = jobs.stopping();
+        jobs.spawn("rate_limit_sweep", async move {
+            tokio::pin!(stopping);
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = &mut stopping => break,
+                    _ = interval.tick() => limiter.sweep(),
+                }
+            }
+            Ok::<(), Infallible>(())
+        });
+    }
+}
+
+// Middleware por grupo: route_layer(from_fn_with_state((limiter, grupo), rate_limit)).
+// Una lectura pública con ?search= no vacío cuenta como búsqueda.
+pub async fn rate_limit(
+    State((limiter, group)): State<(RateLimiter, RateGroup)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let group = if group == RateGroup::PublicRead && has_search(&request) { RateGroup::Search } else { group };
+    let Some(rate) = limiter.rate(group) else {
+        return next.run(request).await;
+    };
+    let Some(key) = client_key(&limiter.inner.proxies, &request) else {
+        return next.run(request).await;
+    };
+
+    match limiter.check(group, rate, key) {
+        Decision::Allowed { remaining, reset } => {
+            let mut response = next.run(request).await;
+            set_headers(response.headers_mut(), rate, remaining, reset);
+            response
+        }
+        Decision::Limited { retry_after, reset } => {
+            let retry_after_secs = ceil_secs(retry_after);
+            tracing::info!("rate_limited group={} retry_after_secs={}", group.as_str(), retry_after_secs);
+            metrics::record_rate_limited(group.as_str());
+
+            let mut response = ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                format!("Demasiadas peticiones: vuelve a intentarlo en {} s", retry_after_secs),
+            )
+            .with_details(json!({ "group": group.as_str(), "retry_after_secs": retry_after_secs }))
+            .into_response();
+            let headers = response.headers_mut();
+            headers.insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+            set_headers(headers, rate, 0, reset);
+            response
+        }
+    }
+}
+
+// El usuario si auth_middleware ya dejó sus claims; si no, la IP. En IPv6 se agrupa por /64,
+// que es lo que suele tener asignado un solo cliente
+fn client_key(proxies: &TrustedProxies, request: &Request) -> Option<ClientKey> {
+    if let Some(claims) = request.extensions().get::<Claims>() {
+        return Some(ClientKey::User(claims.user_id));
+    }
+    let ConnectInfo(addr) = request.extensions().get::<ConnectInfo<SocketAddr>>()?;
+    let ip = proxies.client_ip(request.headers(), addr).parse::<IpAddr>().ok()?;
+    Some(ClientKey::Ip(match ip {
+        IpAddr::V6(v6) => IpAddr::V6(Ipv6Addr::from(u128::from(v6) & !0u128 << 64)),
+        v4 => v4,
+    }))
+}
+
+fn has_search(request: &Request) -> bool {
+    request
+        .uri()
+        .query()
+        .is_some_and(|query| query.split('&').any(|pair| pair.strip_prefix("search=").is_some_and(|v| !v.is_empty())))
+}
+
+fn set_headers(headers: &mut HeaderMap, rate: Rate, remaining: u32, reset: Duration) {
+    headers.insert(HeaderName::from_static(LIMIT_HEADER), HeaderValue::from(rate.requests));
+    headers.insert(HeaderName::from_static(REMAINING_HEADER), HeaderValue::from(remaining));
+    headers.insert(HeaderName::from_static(RESET_HEADER), HeaderValue::from(ceil_secs(reset)));
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs_f64().ceil().max(1.0) as u64
+}
@@ -0,0 +1,105 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{header::USER_AGENT, request::Parts, HeaderMap},
+};
+use ipnet::IpNet;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use crate::error::ApiError;
+
+// [server] trusted_proxies; vive en AppState (ver state.rs). Vacío: no se confía en nadie
+#[derive(Clone, Default)]
+pub struct TrustedProxies(Arc<[IpNet]>);
+
+impl TrustedProxies {
+    pub fn new(nets: &[IpNet]) -> TrustedProxies {
+        if !nets.is_empty() {
+            tracing::info!("Proxies de confianza para X-Forwarded-For: {:?}", nets);
+        }
+        TrustedProxies(nets.into())
+    }
+
+    fn contains(&self, ip: &IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(ip))
+    }
+
+    // IP del cliente. X-Forwarded-For solo cuenta si la conexión viene de un proxy de confianza
+    // (si no, cualquiera podría inventarse la IP y saltarse bloqueos y límites). La cabecera se lee
+    // de derecha a izquierda, que es el orden en que cada proxy añade la IP que le llegó: la primera
+    // que no es de un proxy nuestro es el cliente.
+    pub fn client_ip(&self, headers: &HeaderMap, addr: &SocketAddr) -> String {
+        let mut ip = addr.ip().to_canonical();
+        if !self.contains(&ip) {
+            return ip.to_string();
+        }
+        let forwarded: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect();
+        for hop in forwarded.into_iter().rev() {
+            // Un valor que no es una IP no lo ha puesto un proxy nuestro: nos quedamos con el último
+            // salto conocido
+            let Ok(hop) = hop.parse::<IpAddr>() else { break };
+            ip = hop.to_canonical();
+            if !self.contains(&ip) {
+                break;
+            }
+        }
+        ip.to_string()
+    }
+}
+
+// Extractor con la IP del cliente (TrustedProxies::client_ip sobre ConnectInfo y las cabeceras)
+pub struct ClientIp(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    TrustedProxies: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // Solo falta si el router se sirve sin into_make_service_with_connect_info
+        let ConnectInfo(addr) = parts.extensions.get::<ConnectInfo<SocketAddr>>().ok_or_else(|| {
+            tracing::error!("Petición sin ConnectInfo: no se puede saber la IP del cliente");
+            ApiError::internal()
+        })?;
+        Ok(ClientIp(TrustedProxies::from_ref(state).client_ip(&parts.headers, addr)))
+    }
+}
+
+// IP + User-Agent: el dispositivo con el que se abre una sesión (ver auth::start_session)
+pub struct ClientInfo {
+    pub ip: String,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    TrustedProxies: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ClientIp(ip) = ClientIp::from_request_parts(parts, state).await?;
+        Ok(ClientInfo { ip, user_agent: user_agent(&parts.headers) })
+    }
+}
+
+// User-Agent del navegador/dispositivo (para identificar sesiones)
+pub fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|ua| ua.chars().take(512).collect())
+}
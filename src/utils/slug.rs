@@ -0,0 +1,24 @@
+// Slug URL-safe: minúsculas ASCII y guiones ("Robo en el Centro" -> "robo-en-el-centro")
+pub fn slugify(input: &str) -> String {
+    let mut slug = String::with_capacity(input.len());
+    let mut prev_hyphen = false;
+
+    for ch in input.to_lowercase().chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch);
+            prev_hyphen = false;
+        } else if !prev_hyphen {
+            slug.push('-');
+            prev_hyphen = true;
+        }
+    }
+
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    while slug.starts_with('-') {
+        slug.remove(0);
+    }
+
+    slug
+}
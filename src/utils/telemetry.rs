@@ -0,0 +1,230 @@
+use axum::http::HeaderMap;
+use opentelemetry::{
+    global,
+    propagation::Extractor,
+    trace::{Span as _, SpanKind, TraceContextExt, Tracer as _, TracerProvider as _},
+    Context, KeyValue,
+};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::{Sampler, SdkTracer, SdkTracerProvider},
+    Resource,
+};
+use std::{
+    env,
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime},
+};
+use tracing::{
+    field::{Field, Visit},
+    level_filters::LevelFilter,
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    filter::Targets,
+    layer::Context as LayerContext,
+    registry::LookupSpan,
+    Layer,
+};
+
+// Export de trazas por OTLP/HTTP (protobuf) a un collector (Jaeger, Tempo, otelcol...).
+// Como LOG_FORMAT, se configura solo por entorno porque el subscriber se monta antes de cargar
+// AppConfig; las variables son las estándar de OpenTelemetry:
+//   OTEL_EXPORTER_OTLP_ENDPOINT (p. ej. http://localhost:4318; sin ella no se exporta nada)
+//   o OTEL_EXPORTER_OTLP_TRACES_ENDPOINT (URL completa, .../v1/traces)
+//   OTEL_SERVICE_NAME (por defecto ctv_backend)
+//   OTEL_TRACES_SAMPLER / OTEL_TRACES_SAMPLER_ARG (por defecto parentbased_always_on)
+//
+// Cada petición es un span (el http_request de http_trace, que continúa la traza del
+// `traceparent` entrante) y cada consulta de sqlx un span hijo con su SQL y su duración.
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+    tracer: SdkTracer,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Para no llenar el log de campos otel.* cuando no se exporta nada
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+impl Telemetry {
+    // Ok(None) si no hay endpoint configurado
+    pub fn from_env() -> Result<Option<Telemetry>, String> {
+        let configured = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"]
+            .iter()
+            .any(|name| env::var(name).is_ok_and(|v| !v.is_empty()));
+        if !configured {
+            return Ok(None);
+        }
+
+        let sampler = sampler_from_env()?;
+        // Lee el endpoint, cabeceras y timeout de las OTEL_EXPORTER_OTLP_* estándar
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| format!("OTEL_EXPORTER_OTLP_ENDPOINT: no se pudo crear el exportador: {}", e))?;
+        let service_name = env::var("OTEL_SERVICE_NAME").ok().filter(|s| !s.is_empty());
+
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_sampler(sampler)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(service_name.unwrap_or_else(|| "ctv_backend".into()))
+                    .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+                    .build(),
+            )
+            .build();
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        ENABLED.store(true, Ordering::Relaxed);
+
+        let tracer = provider.tracer("ctv_backend");
+        Ok(Some(Telemetry { provider, tracer }))
+    }
+
+    // Capas para el subscriber de main: los spans de tracing (nivel info o más) pasan a
+    // OpenTelemetry y los eventos de sqlx::query (debug) se convierten en spans de consulta.
+    // Llevan sus propios filtros: RUST_LOG solo decide qué se escribe en el log.
+    pub fn layer<S>(&self) -> impl Layer<S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let spans = tracing_opentelemetry::layer()
+            .with_tracer(self.tracer.clone())
+            .with_filter(Targets::new().with_default(LevelFilter::INFO));
+        let queries = SqlxSpans { tracer: self.tracer.clone() }
+            .with_filter(Targets::new().with_target("sqlx::query", LevelFilter::DEBUG));
+        spans.and_then(queries)
+    }
+
+    // Envía lo que quede en el buffer del batch antes de salir
+    pub fn shutdown(self) {
+        if let Err(e) = self.provider.shutdown() {
+            tracing::warn!("Error cerrando el exportador de trazas: {}", e);
+        }
+    }
+}
+
+// parentbased_* respetan la decisión del `traceparent` entrante; sin padre deciden ellos
+fn sampler_from_env() -> Result<Sampler, String> {
+    let name = env::var("OTEL_TRACES_SAMPLER").unwrap_or_default();
+    let ratio = || -> Result<f64, String> {
+        let arg = env::var("OTEL_TRACES_SAMPLER_ARG").unwrap_or_else(|_| "1.0".into());
+        match arg.trim().parse::<f64>() {
+            Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+            _ => Err(format!("OTEL_TRACES_SAMPLER_ARG: '{}' no es una proporción entre 0 y 1", arg)),
+        }
+    };
+    Ok(match name.as_str() {
+        "" | "parentbased_always_on" => Sampler::ParentBased(Box::new(Sampler::AlwaysOn)),
+        "parentbased_always_off" => Sampler::ParentBased(Box::new(Sampler::AlwaysOff)),
+        "parentbased_traceidratio" => Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(ratio()?))),
+        "always_on" => Sampler::AlwaysOn,
+        "always_off" => Sampler::AlwaysOff,
+        "traceidratio" => Sampler::TraceIdRatioBased(ratio()?),
+        other => {
+            return Err(format!(
+                "OTEL_TRACES_SAMPLER: '{}' desconocido (always_on, always_off, traceidratio, \
+                 parentbased_always_on, parentbased_always_off, parentbased_traceidratio)",
+                other
+            ))
+        }
+    })
+}
+
+// Contexto W3C (traceparent/tracestate) de las cabeceras de la petición. Sin Telemetry el
+// propagador global es un no-op y devuelve un contexto vacío.
+pub fn extract_context(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+// sqlx no abre spans por consulta, pero al terminar cada una emite un evento (target
+// sqlx::query) con el SQL, las filas y el tiempo transcurrido. Con eso se reconstruye el span
+// hijo del span activo (la petición). Las consultas fuera de una petición (arranque, tareas de
+// fondo) no se exportan.
+struct SqlxSpans {
+    tracer: SdkTracer,
+}
+
+impl<S: Subscriber> Layer<S> for SqlxSpans {
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let parent = Context::current();
+        if !parent.has_active_span() {
+            return;
+        }
+        let mut query = QueryFields::default();
+        event.record(&mut query);
+
+        let end = SystemTime::now();
+        let start = end.checked_sub(Duration::from_secs_f64(query.elapsed_secs)).unwrap_or(end);
+        let name = query.summary.trim_end_matches(" …").to_string();
+        // sqlx solo manda el SQL completo si no cabe en el resumen (las 4 primeras palabras)
+        let statement = if query.statement.trim().is_empty() { name.clone() } else { query.statement.trim().to_string() };
+
+        let mut span = self
+            .tracer
+            .span_builder(name)
+            .with_kind(SpanKind::Client)
+            .with_start_time(start)
+            .with_attributes([
+                KeyValue::new("db.system.name", "postgresql"),
+                KeyValue::new("db.query.text", statement),
+                KeyValue::new("db.response.returned_rows", query.rows_returned as i64),
+                KeyValue::new("db.response.affected_rows", query.rows_affected as i64),
+            ])
+            .start_with_context(&self.tracer, &parent);
+        span.end_with_timestamp(end);
+    }
+}
+
+#[derive(Default)]
+struct QueryFields {
+    summary: String,
+    statement: String,
+    rows_returned: u64,
+    rows_affected: u64,
+    elapsed_secs: f64,
+}
+
+impl Visit for QueryFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "summary" => self.summary = value.to_string(),
+            "db.statement" => self.statement = value.to_string(),
+            _ => {}
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "rows_returned" => self.rows_returned = value,
+            "rows_affected" => self.rows_affected = value,
+            _ => {}
+        }
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.elapsed_secs = value;
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+}
@@ -0,0 +1,61 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+// Parámetros estándar que entienden Google Authenticator, Authy, 1Password...
+const ISSUER: &str = "CTV";
+const DIGITS: usize = 6;
+const STEP_SECONDS: u64 = 30;
+const SKEW_STEPS: i64 = 1; // Aceptamos el paso anterior y el siguiente (relojes desfasados)
+const RECOVERY_CODES: usize = 10;
+
+fn build(secret_base32: &str, account: &str) -> Option<TOTP> {
+    let bytes = Secret::Encoded(secret_base32.to_string()).to_bytes().ok()?;
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        0,
+        STEP_SECONDS,
+        bytes,
+        Some(ISSUER.to_string()),
+        account.replace(':', ""),
+    )
+    .ok()
+}
+
+// Nuevo secreto aleatorio de 160 bits en base32
+pub fn generate_secret() -> String {
+    match Secret::generate_secret().to_encoded() {
+        Secret::Encoded(s) => s,
+        Secret::Raw(_) => unreachable!("to_encoded siempre devuelve Encoded"),
+    }
+}
+
+// URI otpauth://totp/... para el QR de la app autenticadora
+pub fn otpauth_uri(secret_base32: &str, account: &str) -> Option<String> {
+    build(secret_base32, account).map(|totp| totp.get_url())
+}
+
+// Verifica el código contra la hora actual. Devuelve el paso aceptado, que debe ser
+// posterior a `last_step` para que un código no se pueda reutilizar.
+pub fn verify(secret_base32: &str, code: &str, last_step: Option<i64>) -> Option<i64> {
+    let totp = build(secret_base32, "")?;
+    let code = code.trim();
+    let now = chrono::Utc::now().timestamp();
+    let current_step = now / STEP_SECONDS as i64;
+
+    (current_step - SKEW_STEPS..=current_step + SKEW_STEPS)
+        .filter(|step| last_step.is_none_or(|last| *step > last))
+        .find(|step| totp.generate((*step as u64) * STEP_SECONDS) == code)
+}
+
+// Códigos de recuperación legibles (ej: "3f9a-c2e1-7b04"), se muestran una sola vez
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES)
+        .map(|_| {
+            let mut bytes = [0u8; 6];
+            OsRng.fill_bytes(&mut bytes);
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{}-{}-{}", &hex[0..4], &hex[4..8], &hex[8..12])
+        })
+        .collect()
+}
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use std::{borrow::Cow, collections::BTreeMap};
+use validator::ValidationError;
+use crate::utils::slug::slugify;
+
+// Validadores propios para `#[validate(custom(function = ...))]` en los schemas de models/.
+// El código del error (p. ej. "password_policy") viaja al cliente junto al mensaje.
+
+// Dominios desde los que aceptamos vídeos embebidos y directos (se admiten subdominios)
+const VIDEO_HOSTS: &[&str] = &[
+    "youtube.com",
+    "youtube-nocookie.com",
+    "youtu.be",
+    "vimeo.com",
+    "facebook.com",
+    "twitch.tv",
+];
+
+pub const ARTICLE_STATUSES: &[&str] = &["draft", "published", "archived"];
+
+fn error(code: &'static str, message: &'static str) -> ValidationError {
+    ValidationError::new(code).with_message(Cow::Borrowed(message))
+}
+
+// Texto con algo más que espacios
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(error("blank", "No puede estar vacío"));
+    }
+    Ok(())
+}
+
+// Mínimo 8 caracteres con al menos una letra y un número
+pub fn password_policy(value: &str) -> Result<(), ValidationError> {
+    let long_enough = value.chars().count() >= 8;
+    let has_letter = value.chars().any(char::is_alphabetic);
+    let has_digit = value.chars().any(|c| c.is_ascii_digit());
+    if !(long_enough && has_letter && has_digit) {
+        return Err(error(
+            "password_policy",
+            "La contraseña debe tener al menos 8 caracteres, con letras y números",
+        ));
+    }
+    Ok(())
+}
+
+// Slug ya normalizado (minúsculas, números y guiones)
+pub fn slug(value: &str) -> Result<(), ValidationError> {
+    if value.is_empty() || slugify(value) != value {
+        return Err(error("slug", "Solo minúsculas, números y guiones"));
+    }
+    Ok(())
+}
+
+pub fn article_status(value: &str) -> Result<(), ValidationError> {
+    if !ARTICLE_STATUSES.contains(&value) {
+        return Err(error("status", "Estado inválido (draft, published o archived)"));
+    }
+    Ok(())
+}
+
+// Imagen subida por /api/upload ("/uploads/...") o URL http(s) absoluta
+pub fn image_url(value: &str) -> Result<(), ValidationError> {
+    if value.starts_with("/uploads/") && !value.contains("..") {
+        return Ok(());
+    }
+    match reqwest::Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host_str().is_some() => Ok(()),
+        _ => Err(error("url", "Debe ser una URL http(s) o una ruta /uploads/...")),
+    }
+}
+
+// Redes del perfil de autor: {"x": "https://..."}, todas URLs http(s)
+pub fn social_links(links: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+    let valid = links.values().all(|value| {
+        matches!(reqwest::Url::parse(value), Ok(url) if matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+    });
+    if !valid {
+        return Err(error("url", "Los enlaces sociales deben ser URLs http(s)"));
+    }
+    Ok(())
+}
+
+// Vídeo embebido o señal en directo: https y dominio de la lista VIDEO_HOSTS
+pub fn video_url(value: &str) -> Result<(), ValidationError> {
+    let url = match reqwest::Url::parse(value) {
+        Ok(url) if url.scheme() == "https" => url,
+        _ => return Err(error("url", "Debe ser una URL https")),
+    };
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    let allowed = VIDEO_HOSTS
+        .iter()
+        .any(|h| host == *h || host.ends_with(&format!(".{}", h)));
+    if !allowed {
+        return Err(error("video_host", "Plataforma de vídeo no permitida (YouTube, Vimeo, Facebook o Twitch)"));
+    }
+    Ok(())
+}
+
+// Fecha de caducidad: tiene que ser futura
+pub fn future_date(value: &DateTime<Utc>) -> Result<(), ValidationError> {
+    if *value <= Utc::now() {
+        return Err(error("past_date", "Debe ser una fecha futura"));
+    }
+    Ok(())
+}